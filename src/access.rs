@@ -0,0 +1,106 @@
+use crate::metadata::validate_id;
+use crate::*;
+
+impl RcsData {
+    /// Adds logins to the access list, like `rcs -alogins`. Logins already on the list are skipped.
+    ///
+    /// ```rust
+    /// use rcs_parser::parse_rcs;
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert!(rcs.has_access("jdoe"));
+    /// rcs.add_access(&["dseres", "alice"]).unwrap();
+    /// assert!(!rcs.has_access("jdoe"));
+    /// assert!(rcs.has_access("alice"));
+    /// ```
+    pub fn add_access(&mut self, logins: &[&str]) -> Result<(), Error> {
+        for login in logins {
+            validate_id(login)?;
+        }
+        for login in logins {
+            if !self.access.iter().any(|id| id == login) {
+                self.access.push(login.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes logins from the access list, like `rcs -elogins`. An empty slice removes everybody,
+    /// so everybody gets access.
+    pub fn remove_access(&mut self, logins: &[&str]) {
+        if logins.is_empty() {
+            self.access.clear();
+        } else {
+            self.access.retain(|id| !logins.contains(&id.as_str()));
+        }
+    }
+
+    /// Appends the access list of another file, like `rcs -Aoldfile`.
+    pub fn copy_access(&mut self, other: &RcsData) -> Result<(), Error> {
+        let logins: Vec<&str> = other.access.iter().map(String::as_str).collect();
+        self.add_access(&logins)
+    }
+
+    /// Checks if a user may lock revisions and check in. An empty access list allows everybody.
+    pub fn has_access(&self, user: &str) -> bool {
+        self.access.is_empty() || self.access.iter().any(|id| id == user)
+    }
+
+    /// Returns [Error::AccessDenied] if the user is not on the access list.
+    ///
+    /// RCS also allows the owner of the file and the superuser; callers knowing them can skip this check.
+    pub fn check_access(&self, user: &str) -> Result<(), Error> {
+        if self.has_access(user) {
+            Ok(())
+        } else {
+            Err(Error::AccessDenied(user.to_string()))
+        }
+    }
+
+    /// Locks a revision like [lock](RcsData::lock), if the user is on the access list.
+    pub fn lock_checked(&mut self, rev: &str, user: &str) -> Result<Num, Error> {
+        self.check_access(user)?;
+        self.lock(rev, user)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn access_list() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.add_access(&["alice", "bob", "alice"]).unwrap();
+        assert_eq!(vec!["alice", "bob"], rcs.access);
+        assert!(matches!(
+            rcs.add_access(&["carol", "x:y"]),
+            Err(Error::InvalidId(_))
+        ));
+        assert_eq!(2, rcs.access.len());
+
+        let mut other = parse_example("text1.txt,v");
+        other.add_access(&["bob", "carol"]).unwrap();
+        rcs.copy_access(&other).unwrap();
+        assert_eq!(vec!["alice", "bob", "carol"], rcs.access);
+
+        rcs.remove_access(&["bob"]);
+        assert_eq!(vec!["alice", "carol"], rcs.access);
+        rcs.remove_access(&[]);
+        assert!(rcs.access.is_empty());
+    }
+
+    #[test]
+    fn lock_checked() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.add_access(&["alice"]).unwrap();
+        assert!(matches!(
+            rcs.lock_checked("1.1", "bob"),
+            Err(Error::AccessDenied(user)) if user == "bob"
+        ));
+        assert_eq!(num![1, 1], rcs.lock_checked("1.1", "alice").unwrap());
+        assert_eq!(num![1, 2], rcs.lock("1.2", "bob").unwrap());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Full name and email address of an author.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Identity {
+    /// Full name, e.g. `David Seres`.
+    pub name: String,
+    /// Email address without angle brackets. It can be empty.
+    pub email: String,
+}
+
+impl fmt::Display for Identity {
+    /// Formats the identity as git does: `Full Name <email>`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+/// Maps the logins of RCS authors to identities.
+///
+/// The file format has one mapping per line: `login = Full Name <email>`.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// ```rust
+/// use rcs_parser::AuthorMap;
+///
+/// let map = AuthorMap::parse("# authors\ndseres = David Seres <dseres001@gmail.com>\n").unwrap();
+/// assert_eq!("David Seres <dseres001@gmail.com>", map.resolve("dseres").to_string());
+/// assert_eq!("guest <>", map.resolve("guest").to_string());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AuthorMap {
+    identities: BTreeMap<String, Identity>,
+}
+
+impl AuthorMap {
+    /// Parses the content of an author map file.
+    pub fn parse(content: &str) -> Result<Self, Error> {
+        let mut map = AuthorMap::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                Error::Parse(format!(
+                    "invalid author mapping in line {}: '{}'",
+                    index + 1,
+                    line
+                ))
+            };
+            let (login, identity) = line.split_once('=').ok_or_else(invalid)?;
+            let identity = identity.trim();
+            let (name, email) = match identity.find('<') {
+                Some(start) => {
+                    let email = identity[start + 1..]
+                        .strip_suffix('>')
+                        .ok_or_else(invalid)?;
+                    (identity[..start].trim(), email.trim())
+                }
+                None => (identity, ""),
+            };
+            let login = login.trim();
+            if login.is_empty() || name.is_empty() {
+                return Err(invalid());
+            }
+            map.insert(login, name, email);
+        }
+        Ok(map)
+    }
+
+    /// Reads an author map file.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::InFile(path.to_path_buf(), Box::new(e.into())))?;
+        Self::parse(&content).map_err(|e| Error::InFile(path.to_path_buf(), Box::new(e)))
+    }
+
+    /// Adds or replaces a mapping.
+    pub fn insert(&mut self, login: &str, name: &str, email: &str) {
+        self.identities.insert(
+            login.to_string(),
+            Identity {
+                name: name.to_string(),
+                email: email.to_string(),
+            },
+        );
+    }
+
+    /// Returns the identity of a login if it is mapped.
+    pub fn get(&self, login: &str) -> Option<&Identity> {
+        self.identities.get(login)
+    }
+
+    /// Returns the identity of a login. Unmapped logins are used as name with an empty email.
+    pub fn resolve(&self, login: &str) -> Identity {
+        self.get(login).cloned().unwrap_or_else(|| Identity {
+            name: login.to_string(),
+            email: String::new(),
+        })
+    }
+
+    /// Returns the logins of a file which are not mapped, with the number of their revisions.
+    pub fn unmapped(&self, rcs: &RcsData) -> BTreeMap<String, usize> {
+        let mut unmapped = BTreeMap::new();
+        for delta in rcs.deltas.values() {
+            if self.get(&delta.author).is_none() {
+                *unmapped.entry(delta.author.clone()).or_insert(0) += 1;
+            }
+        }
+        unmapped
+    }
+
+    /// Scans all comma-v files of a repository, and returns the logins which are not mapped,
+    /// with the number of their revisions.
+    ///
+    /// A comma-v file which cannot be read does not stop the scan: the logins are returned together with the
+    /// errors of such files, each error is an [Error::InFile]. Errors of listing the repository are returned.
+    pub fn unmapped_in_repository(
+        &self,
+        repository: &Path,
+    ) -> Result<(BTreeMap<String, usize>, Vec<Error>), Error> {
+        let mut unmapped = BTreeMap::new();
+        let mut failed = Vec::new();
+        for path in find_rcs_files(repository)? {
+            match read_rcs_file(&path) {
+                Ok(rcs) => {
+                    for (login, count) in self.unmapped(&rcs) {
+                        *unmapped.entry(login).or_insert(0) += count;
+                    }
+                }
+                Err(e) => failed.push(e),
+            }
+        }
+        Ok((unmapped, failed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::broken_repository;
+    use crate::*;
+    use std::path::Path;
+
+    #[test]
+    fn parse() {
+        let map = AuthorMap::parse(
+            "\n# comment\n  jdoe =John Doe<jdoe@example.com>  \nroot = Administrator\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Some(&Identity {
+                name: "John Doe".to_string(),
+                email: "jdoe@example.com".to_string()
+            }),
+            map.get("jdoe")
+        );
+        assert_eq!("Administrator <>", map.resolve("root").to_string());
+        assert!(AuthorMap::parse("jdoe John Doe\n").is_err());
+        assert!(AuthorMap::parse("jdoe = John Doe <jdoe@example.com\n").is_err());
+        assert!(AuthorMap::parse(" = John Doe\n").is_err());
+    }
+
+    #[test]
+    fn unmapped() {
+        let mut map = AuthorMap::default();
+        let (unmapped, failed) = map.unmapped_in_repository(Path::new("examples")).unwrap();
+        assert_eq!(Some(&7), unmapped.get("dseres"));
+        assert!(failed.is_empty());
+        map.insert("dseres", "David Seres", "dseres001@gmail.com");
+        let (unmapped, _) = map.unmapped_in_repository(Path::new("examples")).unwrap();
+        assert!(!unmapped.contains_key("dseres"));
+    }
+
+    #[test]
+    fn unmapped_failed() {
+        let dir = broken_repository("authors", "text1.txt,v");
+        let (unmapped, failed) = AuthorMap::default().unmapped_in_repository(&dir).unwrap();
+        assert_eq!(Some(&7), unmapped.get("dseres"));
+        assert_eq!(1, failed.len());
+        assert!(matches!(&failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use crate::*;
+
+/// The first char of the range holding the bytes which are not valid UTF-8: byte `b` is stored as
+/// `ESCAPE + b - 0x80`. The range is at the end of the last private use plane, so real text hardly uses it.
+const ESCAPE: u32 = 0x10ff80;
+
+/// Parses a comma-v file given as bytes, so files which are not valid UTF-8 can be read too.
+///
+/// Valid UTF-8 is stored as it is in the strings of [RcsData], every other byte is stored as one char of
+/// a private use range. [checkout_bytes](RcsData::checkout_bytes) and [text_to_bytes] give back the original
+/// bytes. This is the way to read binary files (`-kb`), e.g. images or archives, and files in legacy encodings,
+/// which can be decoded by a [Decoder].
+///
+/// Example:
+/// ```rust
+/// use rcs_parser::{parse_rcs_bytes, num, Num};
+///
+/// let contents = std::fs::read("examples/text1.txt,v").unwrap();
+/// let rcs = parse_rcs_bytes(&contents).unwrap();
+/// assert_eq!(rcs.checkout(&num![1, 1]).unwrap().as_bytes(), &rcs.checkout_bytes(&num![1, 1]).unwrap()[..]);
+/// ```
+pub fn parse_rcs_bytes(input: &[u8]) -> Result<RcsData, Error> {
+    let text = bytes_to_text(input);
+    let (_, rcsdata) = parse_rcs(&text).map_err(Error::from_nom)?;
+    Ok(rcsdata)
+}
+
+/// Converts bytes to a text: valid UTF-8 is kept, other bytes are stored as chars of the escape range.
+///
+/// Chars of the escape range found in the input are stored byte by byte too, so [text_to_bytes] gives back
+/// the input exactly.
+pub(crate) fn bytes_to_text(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(rest) {
+            Ok(valid) => (valid, 0),
+            Err(e) => (
+                std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default(),
+                e.error_len().unwrap_or(rest.len() - e.valid_up_to()),
+            ),
+        };
+        for c in valid.chars() {
+            if is_escaped(c) {
+                let mut buf = [0; 4];
+                text.extend(c.encode_utf8(&mut buf).bytes().map(escape));
+            } else {
+                text.push(c);
+            }
+        }
+        let end = valid.len() + invalid;
+        text.extend(rest[valid.len()..end].iter().map(|b| escape(*b)));
+        rest = &rest[end..];
+    }
+    text
+}
+
+/// Returns the char storing a byte which is not valid UTF-8. Only bytes from 0x80 can be invalid.
+fn escape(byte: u8) -> char {
+    char::from_u32(ESCAPE + byte as u32 - 0x80).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Shows if a char stores a byte, see [bytes_to_text].
+pub(crate) fn is_escaped(c: char) -> bool {
+    c as u32 >= ESCAPE
+}
+
+/// Converts a text back to bytes: text is encoded as UTF-8, and chars storing bytes give back the bytes.
+///
+/// It is the inverse of reading the bytes with [parse_rcs_bytes] or [read_rcs_file], so contents and
+/// comma-v files are written exactly as they were read.
+///
+/// ```rust
+/// use rcs_parser::text_to_bytes;
+/// assert_eq!(b"caf\xc3\xa9".to_vec(), text_to_bytes("caf\u{e9}"));
+/// ```
+pub fn text_to_bytes(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut buf = [0; 4];
+    for c in text.chars() {
+        if is_escaped(c) {
+            bytes.push((c as u32 - ESCAPE + 0x80) as u8);
+        } else {
+            bytes.extend(c.encode_utf8(&mut buf).bytes());
+        }
+    }
+    bytes
+}
+
+impl RcsData {
+    /// Reconstructs the content of a revision as bytes, without any keyword substitution.
+    ///
+    /// Binary files are reconstructed byte by byte: diff lines are split at "\n" only and keep their terminators.
+    pub fn checkout_bytes(&self, rev: &Num) -> Result<Vec<u8>, Error> {
+        let lines = self.checkout_lines(rev)?;
+        let mut bytes = Vec::new();
+        for line in lines {
+            bytes.extend(text_to_bytes(&line));
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::path::Path;
+
+    fn binary_file(head: &[u8], diff: &[u8]) -> Vec<u8> {
+        let mut contents =
+            b"head\t1.2;\naccess;\nsymbols;\nlocks; strict;\ncomment\t@# @;\nexpand\t@b@;\n\n\
+            1.2\ndate\t2021.01.02.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t1.1;\n\n\
+            1.1\ndate\t2021.01.01.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t;\n\n\
+            desc\n@@\n\n\
+            1.2\nlog\n@@\ntext\n@"
+                .to_vec();
+        contents.extend(head);
+        contents.extend(b"@\n\n1.1\nlog\n@@\ntext\n@");
+        contents.extend(diff);
+        contents.extend(b"@\n");
+        contents
+    }
+
+    #[test]
+    fn checkout_bytes() {
+        let head = [
+            0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0xff, 0x00, b'$', b'I', b'd', b'$',
+        ];
+        let diff = b"d2 1\na2 1\n\xfe\xfe\xc3\n";
+        let rcs = parse_rcs_bytes(&binary_file(&head, diff)).unwrap();
+        assert!(rcs.is_binary());
+        assert_eq!(&head[..], &rcs.checkout_bytes(&num![1, 2]).unwrap()[..]);
+        let old = [
+            0x89, b'P', b'N', b'G', b'\r', b'\n', 0xfe, 0xfe, 0xc3, b'\n', 0xff, 0x00, b'$', b'I',
+            b'd', b'$',
+        ];
+        assert_eq!(&old[..], &rcs.checkout_bytes(&num![1, 1]).unwrap()[..]);
+        assert_eq!(
+            &head[..],
+            &rcs.checkout_expanded(&num![1, 2], None, Path::new("a,v"), None)
+                .unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn bytes_to_text() {
+        let bytes = b"a\xe9 \xc3\xa9 \xf4\x8f\xbe\x80 \xf0\x9f";
+        let text = super::bytes_to_text(bytes);
+        assert_eq!(
+            "a\u{10ffe9} \u{e9} \u{10fff4}\u{10ff8f}\u{10ffbe}\u{10ff80} \u{10fff0}\u{10ff9f}",
+            text
+        );
+        assert_eq!(bytes.to_vec(), text_to_bytes(&text));
+    }
+
+    #[test]
+    fn expand_log_of_bytes() {
+        let mut rcs = parse_rcs_bytes(&binary_file(b"$Log$\n\xe9\n", b"")).unwrap();
+        rcs.expand = None;
+        rcs.deltas.get_mut(&num![1, 2]).unwrap().log = "\u{2192} \u{e9}\n".to_string();
+        assert_eq!(
+            b"$Log: a,v $\n# Revision 1.2  2021/01/02 00:00:00  a\n# \xe2\x86\x92 \xc3\xa9\n#\n\xe9\n".to_vec(),
+            rcs.checkout_expanded(&num![1, 2], None, Path::new("a,v"), None)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn checkout_bytes_with_at() {
+        let head = b"a@@b\x00@@";
+        let rcs = parse_rcs_bytes(&binary_file(head, b"")).unwrap();
+        assert_eq!(
+            b"a@b\x00@".to_vec(),
+            rcs.checkout_bytes(&num![1, 2]).unwrap()
+        );
+    }
+}
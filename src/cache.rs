@@ -0,0 +1,214 @@
+use crate::checkout::{apply_diff, compose_diffs};
+use crate::*;
+use std::collections::HashMap;
+
+/// Reconstructs revisions of a file, caching texts to avoid applying the same deltas again.
+///
+/// Walking from a cached text to a revision, the text of every `interval`-th revision is kept as a checkpoint,
+/// and the deltas from the last checkpoint are composed into one skip delta for each revision on the way.
+/// At most `capacity` texts are kept, the least recently used one is dropped first, together with the skip deltas
+/// based on it. Checking out revisions in an order where a revision follows the one its delta is based on (the trunk
+/// backwards from head, branches forwards) applies every delta once, and checking out a revision near a visited one
+/// applies one skip delta.
+///
+/// ```rust
+/// use rcs_parser::{parse_rcs, CheckoutCache};
+///
+/// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+/// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+/// let mut cache = CheckoutCache::new(&rcs).interval(4).capacity(16);
+/// for num in rcs.rlog_order() {
+///     assert_eq!(rcs.checkout(&num).unwrap(), cache.checkout(&num).unwrap());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CheckoutCache<'a> {
+    rcs: &'a RcsData,
+    parents: HashMap<&'a Num, &'a Num>,
+    interval: usize,
+    capacity: usize,
+    texts: HashMap<Num, (Vec<String>, u64)>,
+    skips: HashMap<Num, (Num, Vec<DiffCommand>)>,
+    clock: u64,
+}
+
+impl<'a> CheckoutCache<'a> {
+    /// Creates a cache keeping every 16th text and at most 64 texts.
+    pub fn new(rcs: &'a RcsData) -> Self {
+        CheckoutCache {
+            rcs,
+            parents: rcs.storage_parents(),
+            interval: 16,
+            capacity: 64,
+            texts: HashMap::new(),
+            skips: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Sets how often texts are kept as checkpoints while applying deltas.
+    pub fn interval(mut self, interval: usize) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// Sets the maximal number of texts kept.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Returns the number of texts kept.
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Checks if no text is kept.
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// Returns a kept text, marking it as recently used.
+    fn get(&mut self, rev: &Num) -> Option<Vec<String>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.texts.get_mut(rev).map(|(lines, used)| {
+            *used = clock;
+            lines.clone()
+        })
+    }
+
+    /// Keeps a text, dropping the least recently used one and the skip deltas based on it if the cache is full.
+    fn insert(&mut self, rev: &Num, lines: &[String]) {
+        self.clock += 1;
+        self.texts.insert(rev.clone(), (lines.to_vec(), self.clock));
+        if self.texts.len() > self.capacity {
+            let oldest = self
+                .texts
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(num, _)| num.clone());
+            if let Some(oldest) = oldest {
+                self.texts.remove(&oldest);
+                self.skips.retain(|_, (base, _)| *base != oldest);
+            }
+        }
+    }
+
+    /// Reconstructs the lines of a revision, like [checkout_lines](RcsData::checkout_lines).
+    pub fn checkout_lines(&mut self, rev: &Num) -> Result<Vec<String>, Error> {
+        let rcs = self.rcs;
+        let rev = &rcs.get_delta(rev)?.num;
+        if let Some(lines) = self.get(rev) {
+            return Ok(lines);
+        }
+        if let Some((base, skip)) = self.skips.get(rev).cloned() {
+            if let Some(lines) = self.get(&base) {
+                let lines =
+                    apply_diff(lines, &skip).ok_or_else(|| Error::InvalidDiff(rev.clone()))?;
+                self.insert(rev, &lines);
+                return Ok(lines);
+            }
+        }
+
+        let mut chain = vec![rev];
+        let mut start = None;
+        while let Some(parent) = self.parents.get(chain.last().unwrap()).copied() {
+            if let Some(lines) = self.get(parent) {
+                start = Some((parent.clone(), lines));
+                break;
+            }
+            if chain.len() > rcs.deltas.len() {
+                return Err(Error::UnknownRevision(rev.clone()));
+            }
+            chain.push(parent);
+        }
+        if start.is_none() && *chain.last().unwrap() != rcs.stored_revision() {
+            return Err(Error::UnknownRevision(rev.clone()));
+        }
+        chain.reverse();
+
+        let (mut base, mut lines) = match start {
+            Some((base, lines)) => (Some(base), lines),
+            None => (None, Vec::new()),
+        };
+        let mut skip = Vec::new();
+        for (i, num) in chain.into_iter().enumerate() {
+            let checkpoint = match &rcs.get_delta(num)?.text {
+                Text::Head(text) => {
+                    lines = split_lines(text);
+                    true
+                }
+                Text::Diff(commands) => {
+                    lines = apply_diff(lines, commands)
+                        .ok_or_else(|| Error::InvalidDiff(num.clone()))?;
+                    if base.is_some() {
+                        skip = compose_diffs(&skip, commands)
+                            .ok_or_else(|| Error::InvalidDiff(num.clone()))?;
+                    }
+                    (i + 1) % self.interval == 0
+                }
+            };
+            if checkpoint || num == rev {
+                self.insert(num, &lines);
+            }
+            if checkpoint {
+                base = Some(num.clone());
+                skip.clear();
+            } else if let Some(base) = base.as_ref().filter(|base| self.texts.contains_key(base)) {
+                self.skips.insert(num.clone(), (base.clone(), skip.clone()));
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Reconstructs the content of a revision, like [checkout](RcsData::checkout).
+    pub fn checkout(&mut self, rev: &Num) -> Result<String, Error> {
+        Ok(self.checkout_lines(rev)?.concat())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn checkout() {
+        for file in ["text1.txt,v", "vendor.c,v", "removed.c,v", "vendoronly.c,v"] {
+            let rcs = parse_example(file);
+            let mut cache = CheckoutCache::new(&rcs).interval(2).capacity(2);
+            let mut order: Vec<Num> = rcs.deltas.keys().cloned().collect();
+            order.extend(order.clone().into_iter().rev());
+            for num in order {
+                assert_eq!(rcs.checkout(&num).unwrap(), cache.checkout(&num).unwrap());
+                assert!(cache.len() <= 2);
+                assert!(cache
+                    .skips
+                    .values()
+                    .all(|(base, _)| cache.texts.contains_key(base)));
+                assert!(cache.skips.len() <= 2 * 2);
+            }
+        }
+    }
+
+    #[test]
+    fn checkout_skip() {
+        let rcs = parse_example("text1.txt,v");
+        let mut cache = CheckoutCache::new(&rcs).interval(2).capacity(3);
+        assert!(cache.is_empty());
+        let text = cache.checkout(&num![1, 2, 2, 3]).unwrap();
+        assert_eq!(3, cache.len());
+        assert!(cache.texts.contains_key(&num![1, 2, 2, 2]));
+        assert_eq!(
+            Some(&num![1, 2]),
+            cache.skips.get(&num![1, 2, 2, 1]).map(|(base, _)| base)
+        );
+        assert_eq!(
+            rcs.checkout(&num![1, 2, 2, 1]).unwrap(),
+            cache.checkout(&num![1, 2, 2, 1]).unwrap()
+        );
+        assert_eq!(text, cache.checkout(&num![1, 2, 2, 3]).unwrap());
+        assert!(cache.checkout(&num![1, 9]).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::binary::bytes_to_text;
+use crate::date::date_from_unix_seconds;
+use crate::metadata::{clean_log, validate_id};
+use crate::textdiff::to_diff_commands;
+use crate::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A new revision to check in with [checkin](RcsData::checkin), like `ci`.
+///
+/// ```rust
+/// use rcs_parser::{parse_rcs, num, CheckIn, Num};
+///
+/// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+/// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+/// let checkin = CheckIn::new("dseres", b"The Way\n").log("shortened");
+/// assert_eq!(num![2, 2], rcs.checkin(&checkin).unwrap());
+/// assert_eq!("The Way\n", rcs.checkout(&num![2, 2]).unwrap());
+/// assert!(rcs.locks.is_empty());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CheckIn {
+    author: String,
+    content: Vec<u8>,
+    log: String,
+    date: Option<Num>,
+    state: Option<String>,
+    revision: Option<String>,
+    keep_lock: bool,
+    enforce_access: bool,
+    force: bool,
+}
+
+impl CheckIn {
+    /// Creates a check-in of the content by the author. Everything else has the defaults of `ci`.
+    pub fn new(author: &str, content: &[u8]) -> Self {
+        CheckIn {
+            author: author.to_string(),
+            content: content.to_vec(),
+            log: String::new(),
+            date: None,
+            state: None,
+            revision: None,
+            keep_lock: false,
+            enforce_access: false,
+            force: false,
+        }
+    }
+
+    /// Sets the log message, like `ci -mmsg`.
+    pub fn log(mut self, log: &str) -> Self {
+        self.log = log.to_string();
+        self
+    }
+
+    /// Sets the date of the revision, like `ci -ddate`. The default is the current time.
+    pub fn date(mut self, date: Num) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Sets the state of the revision, like `ci -sstate`. The default is `Exp`.
+    pub fn state(mut self, state: &str) -> Self {
+        self.state = Some(state.to_string());
+        self
+    }
+
+    /// Sets the revision the new revision is based on, given by a number or a symbolic name.
+    ///
+    /// The default is the revision locked by the author, or the newest revision of the default branch.
+    /// If the revision is the newest one of its branch, the new revision continues the branch, otherwise
+    /// a new branch is started.
+    pub fn revision(mut self, rev: &str) -> Self {
+        self.revision = Some(rev.to_string());
+        self
+    }
+
+    /// Keeps the new revision locked by the author, like `ci -l`.
+    pub fn keep_lock(mut self, keep_lock: bool) -> Self {
+        self.keep_lock = keep_lock;
+        self
+    }
+
+    /// Refuses the check-in with [Error::AccessDenied] if the author is not on the access list.
+    pub fn enforce_access(mut self, enforce_access: bool) -> Self {
+        self.enforce_access = enforce_access;
+        self
+    }
+
+    /// Checks in the content even if it is unchanged, like `ci -f`.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+}
+
+impl RcsData {
+    /// Returns the revision a check-in of the user is based on.
+    fn checkin_parent(&self, checkin: &CheckIn) -> Result<Num, Error> {
+        if let Some(rev) = &checkin.revision {
+            return self.resolve_revision(rev);
+        }
+        let mut locked = self.locks.iter().filter(|(id, _)| *id == checkin.author);
+        match (locked.next(), locked.next()) {
+            (Some((_, num)), None) => Ok(num.clone()),
+            (Some(_), Some(_)) => Err(Error::MultipleLocks(checkin.author.clone())),
+            (None, _) => self.default_head(),
+        }
+    }
+
+    /// Returns the number of a new revision based on a parent: the next revision of its branch if the parent is
+    /// the newest one, otherwise the first revision of a new branch.
+    fn new_revision_number(&self, parent: &Num) -> Num {
+        let is_tip = *parent == self.head
+            || (parent.numbers.len() > 2 && self.deltas[parent].next.is_none());
+        let mut numbers = parent.numbers.clone();
+        if is_tip {
+            *numbers.last_mut().unwrap() += 1;
+            return Num { numbers };
+        }
+        let level = parent.numbers.len();
+        let reserved = self
+            .symbols
+            .iter()
+            .map(|(_, num)| num.to_real_branch())
+            .filter(|num| num.is_branch() && num.get_branching_point() == *parent);
+        let last = self.deltas[parent]
+            .branches
+            .iter()
+            .cloned()
+            .chain(reserved)
+            .filter_map(|num| num.numbers.get(level).cloned())
+            .max()
+            .unwrap_or(0);
+        numbers.extend([last + 1, 1]);
+        Num { numbers }
+    }
+
+    /// Checks in a new revision, like `ci`. Returns the number of the new revision.
+    ///
+    /// The revision it is based on must be locked by the author, unless strict locking is off and nobody
+    /// locked it. The lock is released, unless [keep_lock](CheckIn::keep_lock) is set. A revision on the trunk
+    /// becomes the head and the delta of the previous head is turned into a reverse diff; a revision on a branch,
+    /// or on the trunk of a re-headed file, is stored as a diff from its parent.
+    ///
+    /// Content which is the same as the revision it is based on is refused with [Error::Unchanged], unless
+    /// [force](CheckIn::force) is set. Content which is not valid UTF-8 is stored byte by byte, as by
+    /// [parse_rcs_bytes].
+    pub fn checkin(&mut self, checkin: &CheckIn) -> Result<Num, Error> {
+        if checkin.enforce_access {
+            self.check_access(&checkin.author)?;
+        }
+        validate_id(&checkin.author)?;
+        let state = checkin.state.clone().unwrap_or_else(|| "Exp".to_string());
+        validate_id(&state)?;
+        let parent = self.checkin_parent(checkin)?;
+        match self.locker(&parent) {
+            Some(locker) if locker == checkin.author => {}
+            Some(locker) => return Err(Error::Locked(parent, locker.to_string())),
+            None if self.strict => return Err(Error::NotLocked(parent)),
+            None => {}
+        }
+        let text = bytes_to_text(&checkin.content);
+        let lines = split_lines(&text);
+        let parent_lines = self.checkout_lines(&parent)?;
+        if lines == parent_lines && !checkin.force {
+            return Err(Error::Unchanged(parent));
+        }
+        let date = match &checkin.date {
+            Some(date) => date.clone(),
+            None => date_from_unix_seconds(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0),
+            ),
+        };
+
+        let num = self.new_revision_number(&parent);
+        let mut delta = Delta {
+            num: num.clone(),
+            date,
+            author: checkin.author.clone(),
+            state: Some(state),
+            branches: Vec::new(),
+            next: None,
+            commitid: None,
+            log: clean_log(&checkin.log),
+            text: Text::Head(text),
+        };
+        let parent_delta = self.deltas.get_mut(&parent).unwrap();
+        if num.numbers.len() == 2 {
+            // In a re-headed file the parent is stored as a diff, the new revision is stored as a diff from it.
+            match parent_delta.text {
+                Text::Head(_) => {
+                    parent_delta.text = Text::Diff(to_diff_commands(&lines, &parent_lines))
+                }
+                Text::Diff(_) => delta.text = Text::Diff(to_diff_commands(&parent_lines, &lines)),
+            }
+            delta.next = Some(parent.clone());
+            self.head = num.clone();
+        } else {
+            if num.numbers.len() == parent.numbers.len() {
+                parent_delta.next = Some(num.clone());
+            } else {
+                parent_delta.branches.push(num.clone());
+            }
+            delta.text = Text::Diff(to_diff_commands(&parent_lines, &lines));
+        }
+        self.deltas.insert(num.clone(), delta);
+
+        let author = &checkin.author;
+        self.locks.retain(|(id, n)| !(id == author && *n == parent));
+        if checkin.keep_lock {
+            self.locks.insert(0, (author.clone(), num.clone()));
+        }
+        Ok(num)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{contents, parse_example};
+    use crate::*;
+
+    #[test]
+    fn checkin_trunk() {
+        let mut rcs = parse_example("text1.txt,v");
+        let expected = contents(&rcs);
+        let checkin = CheckIn::new("dseres", b"a\nb\n")
+            .date(num![2021, 5, 1, 12, 0, 0])
+            .keep_lock(true);
+        assert_eq!(num![2, 2], rcs.checkin(&checkin).unwrap());
+        assert_eq!(num![2, 2], rcs.head);
+        assert_eq!(vec![("dseres".to_string(), num![2, 2])], rcs.locks);
+        let delta = &rcs.deltas[&num![2, 2]];
+        assert_eq!(Some(num![2, 1]), delta.next);
+        assert_eq!(Some("Exp".to_string()), delta.state);
+        assert_eq!("*** empty log message ***\n", delta.log);
+        assert_eq!("a\nb\n", rcs.checkout(&num![2, 2]).unwrap());
+        let mut written = contents(&rcs);
+        written.retain(|(num, _)| *num != num![2, 2]);
+        assert_eq!(expected, written);
+
+        let written = String::from_utf8(rcs.to_bytes()).unwrap();
+        assert_eq!(rcs, parse_rcs(written.as_str()).unwrap().1);
+    }
+
+    #[test]
+    fn checkin_branch() {
+        let mut rcs = parse_example("text1.txt,v");
+        let expected = contents(&rcs);
+        let checkin = CheckIn::new("jdoe", b"fix\n").revision("Fix2");
+        assert!(matches!(rcs.checkin(&checkin), Err(Error::NotLocked(_))));
+        assert!(matches!(
+            rcs.checkin(&checkin.clone().revision("2.1")),
+            Err(Error::Locked(_, _))
+        ));
+        rcs.set_strict(false);
+        assert_eq!(num![1, 2, 2, 4], rcs.checkin(&checkin).unwrap());
+        assert_eq!(Some(num![1, 2, 2, 4]), rcs.deltas[&num![1, 2, 2, 3]].next);
+        assert_eq!(
+            num![1, 2, 3, 1],
+            rcs.checkin(&checkin.clone().revision("1.2")).unwrap()
+        );
+        assert_eq!(
+            num![1, 1, 1, 1],
+            rcs.checkin(&checkin.clone().revision("1.1")).unwrap()
+        );
+        for num in [num![1, 2, 2, 4], num![1, 2, 3, 1], num![1, 1, 1, 1]] {
+            assert_eq!("fix\n", rcs.checkout(&num).unwrap());
+        }
+        let mut written = contents(&rcs);
+        written.retain(|(num, _)| expected.iter().any(|(n, _)| n == num));
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn checkin_access() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.add_access(&["alice"]).unwrap();
+        let checkin = CheckIn::new("dseres", b"text\n").enforce_access(true);
+        assert!(matches!(rcs.checkin(&checkin), Err(Error::AccessDenied(_))));
+        assert_eq!(
+            num![2, 2],
+            rcs.checkin(&checkin.enforce_access(false)).unwrap()
+        );
+    }
+
+    #[test]
+    fn checkin_unchanged() {
+        let mut rcs = parse_example("text1.txt,v");
+        let content = rcs.checkout(&num![2, 1]).unwrap();
+        let checkin = CheckIn::new("dseres", content.as_bytes());
+        assert!(matches!(rcs.checkin(&checkin), Err(Error::Unchanged(n)) if n == num![2, 1]));
+        assert_eq!(num![2, 2], rcs.checkin(&checkin.force(true)).unwrap());
+        assert_eq!(content, rcs.checkout(&num![2, 2]).unwrap());
+    }
+
+    #[test]
+    fn checkin_bytes() {
+        let mut rcs = parse_example("text1.txt,v");
+        let content = b"caf\xe9\n\xff\xfe\n";
+        assert_eq!(
+            num![2, 2],
+            rcs.checkin(&CheckIn::new("dseres", content)).unwrap()
+        );
+        assert_eq!(content.to_vec(), rcs.checkout_bytes(&num![2, 2]).unwrap());
+        let written = parse_rcs_bytes(&rcs.to_bytes()).unwrap();
+        assert_eq!(rcs, written);
+        assert_eq!(
+            content.to_vec(),
+            written.checkout_bytes(&num![2, 2]).unwrap()
+        );
+    }
+}
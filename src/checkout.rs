@@ -0,0 +1,471 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Splits a text to lines. Each line keeps its terminator, so concatenating the lines gives back the text.
+///
+/// ```rust
+/// use rcs_parser::split_lines;
+/// assert_eq!(vec!["a\n", "b\r\n", "c"], split_lines("a\nb\r\nc"));
+/// ```
+pub fn split_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(String::from).collect()
+}
+
+/// Applies diff commands to the lines of the parent revision.
+///
+/// Positions of the commands refer to the lines of the parent, as in RCS diff format.
+/// `None` is returned if the commands do not fit to the lines.
+pub(crate) fn apply_diff(lines: Vec<String>, commands: &[DiffCommand]) -> Option<Vec<String>> {
+    let mut result = Vec::with_capacity(lines.len());
+    let mut source = lines.into_iter();
+    let mut pos = 0u32;
+    for command in commands {
+        match command {
+            DiffCommand::Delete(line, count) => {
+                if *line == 0 || *line - 1 < pos {
+                    return None;
+                }
+                while pos < *line - 1 {
+                    result.push(source.next()?);
+                    pos += 1;
+                }
+                for _ in 0..*count {
+                    source.next()?;
+                }
+                pos += count;
+            }
+            DiffCommand::Add(line, added) => {
+                if *line < pos {
+                    return None;
+                }
+                while pos < *line {
+                    result.push(source.next()?);
+                    pos += 1;
+                }
+                result.extend(added.iter().cloned());
+            }
+            DiffCommand::Head(_) => return None,
+        }
+    }
+    result.extend(source);
+    Some(result)
+}
+
+/// A part of a text produced by diff commands: source lines from a position to an end (the rest of the source
+/// if `None`), or added lines.
+#[derive(Debug, Clone)]
+enum Segment {
+    Copy(u32, Option<u32>),
+    Insert(Vec<String>),
+}
+
+impl Segment {
+    fn len(&self) -> Option<u32> {
+        match self {
+            Segment::Copy(start, end) => end.map(|end| end - start),
+            Segment::Insert(lines) => Some(lines.len() as u32),
+        }
+    }
+}
+
+/// Returns the segments of the text produced by diff commands. The last segment copies the rest of the source.
+fn segments(commands: &[DiffCommand]) -> Option<Vec<Segment>> {
+    let mut result = Vec::new();
+    let mut pos = 0u32;
+    for command in commands {
+        match command {
+            DiffCommand::Delete(line, count) => {
+                if *line == 0 || *line - 1 < pos {
+                    return None;
+                }
+                if *line - 1 > pos {
+                    result.push(Segment::Copy(pos, Some(*line - 1)));
+                }
+                pos = *line - 1 + count;
+            }
+            DiffCommand::Add(line, added) => {
+                if *line < pos {
+                    return None;
+                }
+                if *line > pos {
+                    result.push(Segment::Copy(pos, Some(*line)));
+                }
+                pos = *line;
+                result.push(Segment::Insert(added.clone()));
+            }
+            DiffCommand::Head(_) => return None,
+        }
+    }
+    result.push(Segment::Copy(pos, None));
+    Some(result)
+}
+
+/// Returns the segments producing lines `start..end` of the text produced by other segments.
+fn slice_segments(segments: &[Segment], start: u32, end: Option<u32>) -> Vec<Segment> {
+    let mut result = Vec::new();
+    let mut offset = 0u32;
+    for segment in segments {
+        let len = segment.len();
+        let from = start.max(offset);
+        let to = match (end, len) {
+            (Some(end), Some(len)) => Some(end.min(offset + len)),
+            (Some(end), None) => Some(end),
+            (None, Some(len)) => Some(offset + len),
+            (None, None) => None,
+        };
+        if to.map_or(true, |to| from < to) {
+            result.push(match segment {
+                Segment::Copy(source, source_end) => Segment::Copy(
+                    source + from - offset,
+                    to.map(|to| source + to - offset).or(*source_end),
+                ),
+                Segment::Insert(lines) => Segment::Insert(
+                    lines[(from - offset) as usize..(to.unwrap() - offset) as usize].to_vec(),
+                ),
+            });
+        }
+        match len {
+            Some(len) if end.map_or(true, |end| offset + len < end) => offset += len,
+            _ => break,
+        }
+    }
+    result
+}
+
+/// Composes two lists of diff commands: applying the result gives the same text as applying `first`, then `second`.
+///
+/// `None` is returned if the commands are not in order.
+pub(crate) fn compose_diffs(
+    first: &[DiffCommand],
+    second: &[DiffCommand],
+) -> Option<Vec<DiffCommand>> {
+    let first = segments(first)?;
+    let mut composed = Vec::new();
+    for segment in segments(second)? {
+        match segment {
+            Segment::Copy(start, end) => composed.extend(slice_segments(&first, start, end)),
+            insert => composed.push(insert),
+        }
+    }
+    let mut commands = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut pos = 0u32;
+    for segment in composed {
+        match segment {
+            Segment::Insert(lines) => pending.extend(lines),
+            Segment::Copy(start, end) => {
+                if start > pos {
+                    commands.push(DiffCommand::Delete(pos + 1, start - pos));
+                }
+                if !pending.is_empty() {
+                    commands.push(DiffCommand::Add(start, std::mem::take(&mut pending)));
+                }
+                match end {
+                    Some(end) => pos = end,
+                    None => break,
+                }
+            }
+        }
+    }
+    Some(commands)
+}
+
+impl RcsData {
+    /// Returns a delta or [Error::UnknownRevision] if the revision does not exist.
+    pub fn get_delta(&self, rev: &Num) -> Result<&Delta, Error> {
+        self.deltas
+            .get(rev)
+            .ok_or_else(|| Error::UnknownRevision(rev.clone()))
+    }
+
+    /// Returns the revision stored in full, the one whose text is [Text::Head]. It is the head, unless the file
+    /// was re-headed with [rehead](RcsData::rehead).
+    pub fn stored_revision(&self) -> &Num {
+        self.deltas
+            .values()
+            .find(|delta| matches!(delta.text, Text::Head(_)))
+            .map_or(&self.head, |delta| &delta.num)
+    }
+
+    /// Returns the parent of each revision by the links: the revision whose `next` or `branches` refers to it.
+    pub(crate) fn link_parents(&self) -> HashMap<&Num, &Num> {
+        let mut parents = HashMap::new();
+        for delta in self.deltas.values() {
+            for child in delta.next.iter().chain(delta.branches.iter()) {
+                parents.insert(child, &delta.num);
+            }
+        }
+        parents
+    }
+
+    /// Returns the parent of each revision in the storage tree. The delta of a revision is applied to the text of
+    /// its parent, the [stored revision](RcsData::stored_revision) has no parent.
+    ///
+    /// The storage tree is the tree of the links, with the links between the head and the stored revision reversed.
+    pub(crate) fn storage_parents(&self) -> HashMap<&Num, &Num> {
+        let mut parents = self.link_parents();
+        let mut current = self.stored_revision();
+        let mut child = None;
+        for _ in 0..self.deltas.len() {
+            let parent = parents.get(current).copied();
+            match child {
+                Some(child) => parents.insert(current, child),
+                None => parents.remove(current),
+            };
+            match parent {
+                Some(parent) => {
+                    child = Some(current);
+                    current = parent;
+                }
+                None => break,
+            }
+        }
+        parents
+    }
+
+    /// Returns the children of each revision in the storage tree, the branches before the `next` revision.
+    pub(crate) fn storage_children(&self) -> HashMap<&Num, Vec<&Num>> {
+        let parents = self.storage_parents();
+        let mut children: HashMap<&Num, Vec<&Num>> = HashMap::new();
+        for delta in self.deltas.values() {
+            for child in delta.branches.iter().chain(delta.next.iter()) {
+                if parents.get(child) == Some(&&delta.num) {
+                    children.entry(&delta.num).or_default().push(child);
+                } else {
+                    children.entry(child).or_default().push(&delta.num);
+                }
+            }
+        }
+        children
+    }
+
+    /// Returns the revisions which deltas are applied to reconstruct a revision, starting with the stored revision.
+    ///
+    /// The storage links are followed from the stored revision: in files written by RCS the trunk is walked
+    /// backwards from head to the revision or to its branching point, then each branch forwards.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(
+    ///     vec![num![2, 1], num![1, 2], num![1, 2, 2, 1], num![1, 2, 2, 2]],
+    ///     rcs.revision_path(&num![1, 2, 2, 2]).unwrap()
+    /// );
+    /// ```
+    pub fn revision_path(&self, rev: &Num) -> Result<Vec<Num>, Error> {
+        self.get_delta(rev)?;
+        let parents = self.storage_parents();
+        let mut path = vec![rev.clone()];
+        let mut current = rev;
+        while let Some(parent) = parents.get(current) {
+            if path.len() > self.deltas.len() {
+                return Err(Error::UnknownRevision(rev.clone()));
+            }
+            self.get_delta(parent)?;
+            path.push((*parent).clone());
+            current = parent;
+        }
+        if current != self.stored_revision() {
+            return Err(Error::UnknownRevision(rev.clone()));
+        }
+        path.reverse();
+        Ok(path)
+    }
+
+    /// Returns the revisions of a branch, from the oldest to the newest.
+    ///
+    /// A branch number with one component, e.g. `1`, means the revisions of the trunk starting with that number.
+    /// An empty num means the whole trunk.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(vec![num![1, 1], num![1, 2], num![2, 1]], rcs.branch_revisions(&num![]).unwrap());
+    /// assert_eq!(vec![num![1, 2, 2, 1], num![1, 2, 2, 2], num![1, 2, 2, 3]], rcs.branch_revisions(&num![1, 2, 2]).unwrap());
+    /// ```
+    pub fn branch_revisions(&self, branch: &Num) -> Result<Vec<Num>, Error> {
+        let mut revisions = Vec::new();
+        if branch.numbers.len() <= 1 {
+            let mut current = Some(self.head.clone());
+            while let Some(num) = current {
+                current = self.get_delta(&num)?.next.clone();
+                if branch.numbers.is_empty() || branch.numbers[0] == num.numbers[0] {
+                    revisions.push(num);
+                }
+            }
+            revisions.reverse();
+            return Ok(revisions);
+        }
+        if !branch.is_branch() {
+            return Err(Error::UnknownRevision(branch.clone()));
+        }
+        let mut current = self
+            .get_delta(&branch.get_branching_point())?
+            .branches
+            .iter()
+            .find(|b| {
+                b.numbers.len() == branch.numbers.len() + 1
+                    && b.numbers.starts_with(&branch.numbers)
+            })
+            .cloned();
+        while let Some(num) = current {
+            current = self.get_delta(&num)?.next.clone();
+            revisions.push(num);
+        }
+        Ok(revisions)
+    }
+
+    /// Returns the numbers of all branches in the file, ordered by number.
+    pub fn branches(&self) -> Vec<Num> {
+        let mut branches: Vec<Num> = self
+            .deltas
+            .values()
+            .flat_map(|d| d.branches.iter().map(Num::get_branch))
+            .collect();
+        branches.sort();
+        branches.dedup();
+        branches
+    }
+
+    /// Returns the newest revision of a branch. If the branch has no revisions, its branching point is returned.
+    pub fn branch_tip(&self, branch: &Num) -> Result<Num, Error> {
+        match self.branch_revisions(branch)?.pop() {
+            Some(num) => Ok(num),
+            None if branch.numbers.len() > 1 => Ok(branch.get_branching_point()),
+            None => Err(Error::UnknownRevision(branch.clone())),
+        }
+    }
+
+    /// Reconstructs the lines of a revision. Lines keep their terminators.
+    pub fn checkout_lines(&self, rev: &Num) -> Result<Vec<String>, Error> {
+        let path = self.revision_path(rev)?;
+        let mut lines = Vec::new();
+        for num in path {
+            lines = match &self.get_delta(&num)?.text {
+                Text::Head(text) => split_lines(text),
+                Text::Diff(commands) => {
+                    apply_diff(lines, commands).ok_or(Error::InvalidDiff(num))?
+                }
+            };
+        }
+        Ok(lines)
+    }
+
+    /// Reconstructs the content of a revision exactly as it was checked in.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let text = rcs.checkout(&num![1, 1]).unwrap();
+    /// assert!(text.starts_with("The Way that can be told of is not the eternal Way;\n"));
+    /// ```
+    pub fn checkout(&self, rev: &Num) -> Result<String, Error> {
+        Ok(self.checkout_lines(rev)?.concat())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    fn lines(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn apply_diff() {
+        let source = lines(&["a\n", "b\n", "c\n", "d\n"]);
+        let commands = vec![
+            DiffCommand::Delete(1, 1),
+            DiffCommand::Add(2, lines(&["x\r\n", "y\n"])),
+            DiffCommand::Delete(4, 1),
+            DiffCommand::Add(4, lines(&["z"])),
+        ];
+        assert_eq!(
+            Some(lines(&["b\n", "x\r\n", "y\n", "c\n", "z"])),
+            super::apply_diff(source.clone(), &commands)
+        );
+        assert_eq!(
+            None,
+            super::apply_diff(source.clone(), &[DiffCommand::Delete(4, 2)])
+        );
+        assert_eq!(
+            None,
+            super::apply_diff(
+                source,
+                &[DiffCommand::Delete(3, 1), DiffCommand::Delete(1, 1)]
+            )
+        );
+    }
+
+    #[test]
+    fn compose_diffs() {
+        let texts = [
+            "a\nb\nc\nd\n",
+            "b\nx\nc\nd\ne\n",
+            "x\nb\nd\n",
+            "",
+            "y\nz",
+            "x\nb\nd\ny\nz",
+        ];
+        for t0 in texts.iter() {
+            for t1 in texts.iter() {
+                for t2 in texts.iter() {
+                    let (l0, l1, l2) = (split_lines(t0), split_lines(t1), split_lines(t2));
+                    let first = crate::textdiff::to_diff_commands(&l0, &l1);
+                    let second = crate::textdiff::to_diff_commands(&l1, &l2);
+                    let composed = super::compose_diffs(&first, &second).unwrap();
+                    assert_eq!(Some(l2), super::apply_diff(l0, &composed), "{:?}", composed);
+                }
+            }
+        }
+        assert_eq!(
+            None,
+            super::compose_diffs(&[DiffCommand::Delete(3, 1), DiffCommand::Delete(1, 1)], &[])
+        );
+    }
+
+    #[test]
+    fn checkout() {
+        let rcs = parse_example("text1.txt,v");
+        for num in rcs.deltas.keys() {
+            let text = rcs.checkout(num).unwrap();
+            assert!(text.ends_with('\n'));
+        }
+        assert_eq!(
+            rcs.checkout(&num![1, 1]).unwrap(),
+            rcs.checkout(&num![2, 1]).unwrap()
+        );
+        assert!(matches!(
+            rcs.checkout(&num![1, 3]),
+            Err(Error::UnknownRevision(_))
+        ));
+        assert!(matches!(
+            rcs.checkout(&num![1, 2, 3, 1]),
+            Err(Error::UnknownRevision(_))
+        ));
+    }
+
+    #[test]
+    fn checkout_line_endings() {
+        let contents = "head\t1.2;\naccess;\nsymbols;\nlocks;\n\n\
+            1.2\ndate\t2021.01.02.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t1.1;\n\n\
+            1.1\ndate\t2021.01.01.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t;\n\n\
+            desc\n@@\n\n\
+            1.2\nlog\n@@\ntext\n@one\r\ntwo\r\nthree@\n\n\
+            1.1\nlog\n@@\ntext\n@d2 2\na3 2\nold\rmac\r\nlast\n@\n";
+        let (_, rcs) = parse_rcs(contents).unwrap();
+        assert_eq!("one\r\ntwo\r\nthree", rcs.checkout(&num![1, 2]).unwrap());
+        assert_eq!(
+            "one\r\nold\rmac\r\nlast\n",
+            rcs.checkout(&num![1, 1]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{Error, Num};
+
+/// Returns year, month, day, hour, minute and second of a date stored in a delta.
+///
+/// Dates before 2000 are stored with two digit years in RCS files, e.g. 99.12.31.23.59.59.
+/// Missing fields are returned as zero.
+pub(crate) fn date_fields(date: &Num) -> [u32; 6] {
+    let mut fields = [0u32; 6];
+    for (field, n) in fields.iter_mut().zip(date.numbers.iter()) {
+        *field = *n;
+    }
+    if fields[0] < 100 {
+        fields[0] += 1900;
+    }
+    fields
+}
+
+/// Formats a date as RCS shows it in keywords and logs: `2021/03/25 10:16:43`.
+pub(crate) fn format_date(date: &Num) -> String {
+    let [year, month, day, hour, min, sec] = date_fields(date);
+    format!(
+        "{}/{:02}/{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, min, sec
+    )
+}
+
+/// Returns the seconds since 1970-01-01 00:00:00 UTC of date fields.
+pub(crate) fn unix_seconds(date: &[u32; 6]) -> i64 {
+    let [year, month, day, hour, min, sec] = date.map(|n| n as i64);
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let days = 365 * y + y / 4 - y / 100 + y / 400 + (153 * m + 2) / 5 + day - 719469;
+    ((days * 24 + hour) * 60 + min) * 60 + sec
+}
+
+/// Returns the date of seconds since 1970-01-01 00:00:00 UTC, with a four digit year.
+pub(crate) fn date_from_unix_seconds(seconds: i64) -> Num {
+    let (days, secs) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let fields = [year, month, day, secs / 3600, secs / 60 % 60, secs % 60];
+    Num {
+        numbers: fields.iter().map(|n| *n as u32).collect(),
+    }
+}
+
+/// Parses a date given by the user, e.g. `2019-03-01`, `2019/03/01 12:30` or `2019-03-01T12:30:00Z`.
+///
+/// Dates are in UTC as in RCS files. Missing time fields are zero.
+/// The result can be compared with the dates of deltas.
+///
+/// ```rust
+/// use rcs_parser::{parse_date, num, Num};
+/// assert_eq!(num![2019, 3, 1, 0, 0, 0], parse_date("2019-03-01").unwrap());
+/// assert_eq!(num![2019, 3, 1, 12, 30, 0], parse_date("2019/03/01 12:30").unwrap());
+/// assert!(parse_date("yesterday").is_err());
+/// ```
+pub fn parse_date(date: &str) -> Result<Num, Error> {
+    let invalid = || Error::Parse(format!("invalid date '{}'", date));
+    let trimmed = date.trim().trim_end_matches(['Z', 'z']);
+    let trimmed = trimmed.strip_suffix("UTC").unwrap_or(trimmed).trim_end();
+    let fields = trimmed
+        .split(|c: char| "-/.: T".contains(c))
+        .filter(|f| !f.is_empty())
+        .map(|f| f.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u32>, Error>>()?;
+    if fields.len() < 3 || fields.len() > 6 {
+        return Err(invalid());
+    }
+    let mut numbers = [0u32; 6];
+    numbers[..fields.len()].copy_from_slice(&fields);
+    let [_, month, day, hour, min, sec] = numbers;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || min > 59 || sec > 60 {
+        return Err(invalid());
+    }
+    Ok(Num {
+        numbers: date_fields(&Num {
+            numbers: numbers.to_vec(),
+        })
+        .to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn parse_date() {
+        assert_eq!(
+            num![2019, 3, 1, 12, 30, 5],
+            super::parse_date("2019-03-01T12:30:05Z").unwrap()
+        );
+        assert_eq!(
+            num![1999, 12, 31, 0, 0, 0],
+            super::parse_date("99.12.31").unwrap()
+        );
+        assert!(super::parse_date("2019-13-01").is_err());
+        assert!(super::parse_date("2019-03").is_err());
+    }
+
+    #[test]
+    fn unix_seconds() {
+        assert_eq!(0, super::unix_seconds(&[1970, 1, 1, 0, 0, 0]));
+        assert_eq!(951782400, super::unix_seconds(&[2000, 2, 29, 0, 0, 0]));
+    }
+
+    #[test]
+    fn date_from_unix_seconds() {
+        assert_eq!(num![1970, 1, 1, 0, 0, 0], super::date_from_unix_seconds(0));
+        assert_eq!(
+            num![2000, 2, 29, 0, 0, 0],
+            super::date_from_unix_seconds(951782400)
+        );
+        let date = num![2021, 4, 10, 9, 38, 42];
+        let seconds = super::unix_seconds(&super::date_fields(&date));
+        assert_eq!(date, super::date_from_unix_seconds(seconds));
+    }
+
+    #[test]
+    fn format_date() {
+        assert_eq!(
+            "2021/03/25 10:16:43",
+            super::format_date(&num![2021, 3, 25, 10, 16, 43])
+        );
+        assert_eq!(
+            "1999/12/31 23:59:05",
+            super::format_date(&num![99, 12, 31, 23, 59, 5])
+        );
+    }
+}
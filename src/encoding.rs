@@ -0,0 +1,395 @@
+use crate::binary::is_escaped;
+use crate::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Character encodings of texts in comma-v files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Encoding {
+    /// UTF-8.
+    Utf8,
+    /// Windows-1252, Latin-1 with printable characters in the range 0x80-0x9f.
+    Cp1252,
+    /// ISO-8859-1. Every byte sequence is valid Latin-1.
+    Latin1,
+}
+
+/// Characters of CP1252 in the range 0x80-0x9f. Zero means undefined.
+static CP1252: [u16; 32] = [
+    0x20ac, 0, 0x201a, 0x0192, 0x201e, 0x2026, 0x2020, 0x2021, 0x02c6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0, 0x017d, 0, 0, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014, 0x02dc,
+    0x2122, 0x0161, 0x203a, 0x0153, 0, 0x017e, 0x0178,
+];
+
+impl Encoding {
+    /// Parses the name of an encoding, e.g. `utf-8`, `latin1`, `iso-8859-1`, `cp1252` or `windows-1252`.
+    ///
+    /// ```rust
+    /// use rcs_parser::Encoding;
+    /// assert_eq!(Some(Encoding::Cp1252), Encoding::from_name("Windows-1252"));
+    /// assert_eq!(None, Encoding::from_name("ebcdic"));
+    /// ```
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "cp1252" | "windows-1252" => Some(Encoding::Cp1252),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Decodes bytes. `None` is returned if the bytes are not valid in this encoding.
+    ///
+    /// ```rust
+    /// use rcs_parser::Encoding;
+    /// assert_eq!(Some("d\u{e9}j\u{e0}".to_string()), Encoding::Latin1.decode(b"d\xe9j\xe0"));
+    /// assert_eq!(Some("\u{201c}\u{20ac}\u{201d}".to_string()), Encoding::Cp1252.decode(b"\x93\x80\x94"));
+    /// assert_eq!(None, Encoding::Utf8.decode(b"d\xe9j\xe0"));
+    /// ```
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Encoding::Latin1 => Some(bytes.iter().map(|b| *b as char).collect()),
+            Encoding::Cp1252 => bytes.iter().map(|b| cp1252_char(*b)).collect(),
+        }
+    }
+
+    /// Decodes bytes, replacing invalid sequences with U+FFFD.
+    pub fn decode_lossy(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Latin1 => bytes.iter().map(|b| *b as char).collect(),
+            Encoding::Cp1252 => bytes
+                .iter()
+                .map(|b| cp1252_char(*b).unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
+        }
+    }
+}
+
+fn cp1252_char(byte: u8) -> Option<char> {
+    match byte {
+        0x80..=0x9f => match CP1252[byte as usize - 0x80] {
+            0 => None,
+            code => char::from_u32(code as u32),
+        },
+        _ => Some(byte as char),
+    }
+}
+
+/// A revision whose log or text was not valid UTF-8.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Recoded {
+    /// The revision, `None` for the description of the file.
+    pub revision: Option<Num>,
+    /// The encoding which was used to decode it.
+    pub encoding: Encoding,
+    /// Shows that some bytes could not be decoded and were replaced with U+FFFD.
+    pub lossy: bool,
+}
+
+/// Decodes the logs, the description and the contents of comma-v files with a fallback chain of encodings.
+///
+/// The encodings of the chain are tried in order, the first one which can decode a text is used.
+/// A file can be given a fixed encoding, which overrides the chain. If no encoding fits, decoding fails,
+/// unless lossy mode is on: then the first encoding of the chain is used, replacing invalid bytes.
+/// Contents of binary files (`-kb`) are never decoded.
+///
+/// ```rust
+/// use rcs_parser::{Decoder, Encoding};
+///
+/// let decoder = Decoder::new(&[Encoding::Utf8, Encoding::Cp1252]).lossy(true);
+/// assert_eq!(
+///     ("caf\u{e9}".to_string(), Encoding::Cp1252, false),
+///     decoder.decode(b"caf\xe9", None).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    chain: Vec<Encoding>,
+    overrides: BTreeMap<PathBuf, Encoding>,
+    lossy: bool,
+}
+
+impl Default for Decoder {
+    /// The chain is UTF-8, CP1252, Latin-1, so every text can be decoded.
+    fn default() -> Self {
+        Decoder::new(&[Encoding::Utf8, Encoding::Cp1252, Encoding::Latin1])
+    }
+}
+
+impl Decoder {
+    /// Creates a decoder with a fallback chain.
+    pub fn new(chain: &[Encoding]) -> Self {
+        Decoder {
+            chain: chain.to_vec(),
+            overrides: BTreeMap::new(),
+            lossy: false,
+        }
+    }
+
+    /// Sets the encoding of a comma-v file, given by its path.
+    pub fn with_override(mut self, rcsfile: &Path, encoding: Encoding) -> Self {
+        self.overrides.insert(rcsfile.to_path_buf(), encoding);
+        self
+    }
+
+    /// Turns lossy mode on or off.
+    pub fn lossy(self, lossy: bool) -> Self {
+        Decoder { lossy, ..self }
+    }
+
+    /// Returns the encodings to try for a file.
+    fn chain_of(&self, rcsfile: Option<&Path>) -> Vec<Encoding> {
+        match rcsfile.and_then(|path| self.overrides.get(path)) {
+            Some(encoding) => vec![*encoding],
+            None => self.chain.clone(),
+        }
+    }
+
+    /// Decodes bytes of a file. Returns the text, the encoding used, and whether decoding was lossy.
+    pub fn decode(
+        &self,
+        bytes: &[u8],
+        rcsfile: Option<&Path>,
+    ) -> Result<(String, Encoding, bool), Error> {
+        self.try_decode(bytes, &self.chain_of(rcsfile))
+            .ok_or(Error::Decode(None))
+    }
+
+    fn try_decode(&self, bytes: &[u8], chain: &[Encoding]) -> Option<(String, Encoding, bool)> {
+        for encoding in chain {
+            if let Some(text) = encoding.decode(bytes) {
+                return Some((text, *encoding, false));
+            }
+        }
+        match chain.first() {
+            Some(encoding) if self.lossy => Some((encoding.decode_lossy(bytes), *encoding, true)),
+            _ => None,
+        }
+    }
+
+    /// Decodes the texts of a file parsed by [parse_rcs_bytes] which hold bytes that are not valid UTF-8.
+    ///
+    /// The log and the text of each revision, and the description are decoded separately.
+    /// Only the logs and the description of binary files are decoded, their contents stay bytes.
+    /// Returns the revisions which were not valid UTF-8. Texts which are valid UTF-8 are not changed,
+    /// so decoding a file twice gives the same result.
+    pub fn decode_rcs(
+        &self,
+        rcs: &mut RcsData,
+        rcsfile: Option<&Path>,
+    ) -> Result<Vec<Recoded>, Error> {
+        let mut report = Vec::new();
+        let chain = self.chain_of(rcsfile);
+        let binary = rcs.is_binary();
+        let decode = |text: &str,
+                      rev: Option<&Num>,
+                      used: &mut Option<(Encoding, bool)>|
+         -> Result<String, Error> {
+            if !text.chars().any(is_escaped) {
+                return Ok(text.to_string());
+            }
+            let bytes = text_to_bytes(text);
+            let (text, encoding, lossy) = self
+                .try_decode(&bytes, &chain)
+                .ok_or_else(|| Error::Decode(rev.cloned()))?;
+            if encoding != Encoding::Utf8 || lossy {
+                let previous = used.unwrap_or((encoding, lossy));
+                *used = Some((previous.0.max(encoding), previous.1 || lossy));
+            }
+            Ok(text)
+        };
+        let mut used = None;
+        let desc = decode(&rcs.desc, None, &mut used)?;
+        if let Some((encoding, lossy)) = used {
+            report.push(Recoded {
+                revision: None,
+                encoding,
+                lossy,
+            });
+        }
+        let mut deltas = BTreeMap::new();
+        for (num, delta) in rcs.deltas.iter() {
+            let mut used = None;
+            let mut delta = delta.clone();
+            delta.log = decode(&delta.log, Some(num), &mut used)?;
+            if !binary {
+                delta.text = match delta.text {
+                    Text::Head(text) => Text::Head(decode(&text, Some(num), &mut used)?),
+                    Text::Diff(commands) => Text::Diff(
+                        commands
+                            .into_iter()
+                            .map(|command| match command {
+                                DiffCommand::Add(line, lines) => Ok(DiffCommand::Add(
+                                    line,
+                                    lines
+                                        .iter()
+                                        .map(|l| decode(l, Some(num), &mut used))
+                                        .collect::<Result<_, Error>>()?,
+                                )),
+                                command => Ok(command),
+                            })
+                            .collect::<Result<_, Error>>()?,
+                    ),
+                };
+            }
+            if let Some((encoding, lossy)) = used {
+                report.push(Recoded {
+                    revision: Some(num.clone()),
+                    encoding,
+                    lossy,
+                });
+            }
+            deltas.insert(num.clone(), delta);
+        }
+        rcs.desc = desc;
+        rcs.deltas = deltas;
+        Ok(report)
+    }
+
+    /// Reads a comma-v file and decodes its texts. Returns the revisions which were not valid UTF-8.
+    pub fn read_rcs_file(&self, path: &Path) -> Result<(RcsData, Vec<Recoded>), Error> {
+        let in_file = |e: Error| Error::InFile(path.to_path_buf(), Box::new(e));
+        let bytes = std::fs::read(path).map_err(|e| in_file(e.into()))?;
+        let mut rcs = parse_rcs_bytes(&bytes).map_err(in_file)?;
+        let report = self.decode_rcs(&mut rcs, Some(path)).map_err(in_file)?;
+        Ok((rcs, report))
+    }
+
+    /// Scans all comma-v files of a repository, and reports the files with revisions which were not valid UTF-8.
+    ///
+    /// A comma-v file which cannot be read or decoded does not stop the scan: the report is returned together with
+    /// the errors of such files, each error is an [Error::InFile]. Errors of listing the repository are returned.
+    #[allow(clippy::type_complexity)]
+    pub fn report_repository(
+        &self,
+        repository: &Path,
+    ) -> Result<(Vec<(PathBuf, Vec<Recoded>)>, Vec<Error>), Error> {
+        let mut result = Vec::new();
+        let mut failed = Vec::new();
+        for path in find_rcs_files(repository)? {
+            match self.read_rcs_file(&path) {
+                Ok((_, report)) if !report.is_empty() => result.push((path, report)),
+                Ok(_) => {}
+                Err(e) => failed.push(e),
+            }
+        }
+        Ok((result, failed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::broken_repository;
+    use crate::*;
+    use std::path::Path;
+
+    fn latin1_file() -> Vec<u8> {
+        let mut contents = std::fs::read("examples/text1.txt,v").unwrap();
+        let log = b"log\n@Fixed the \x93Named\x94 line\n@";
+        let pos = contents
+            .windows(log.len())
+            .position(|w| w.starts_with(b"log\n@"))
+            .unwrap();
+        let end = pos + 5 + contents[pos + 5..].iter().position(|b| *b == b'@').unwrap();
+        contents.splice(pos..end + 1, log.iter().cloned());
+        contents
+    }
+
+    #[test]
+    fn decode() {
+        let decoder = Decoder::new(&[Encoding::Utf8]);
+        assert_eq!(
+            ("\u{e9}".to_string(), Encoding::Utf8, false),
+            decoder.decode("\u{e9}".as_bytes(), None).unwrap()
+        );
+        assert!(matches!(
+            decoder.decode(b"\xe9", None),
+            Err(Error::Decode(None))
+        ));
+        let decoder = decoder.lossy(true);
+        assert_eq!(
+            ("\u{fffd}".to_string(), Encoding::Utf8, true),
+            decoder.decode(b"\xe9", None).unwrap()
+        );
+        let decoder = Decoder::default().with_override(Path::new("a,v"), Encoding::Latin1);
+        assert_eq!(
+            ("\u{93}".to_string(), Encoding::Latin1, false),
+            decoder.decode(b"\x93", Some(Path::new("a,v"))).unwrap()
+        );
+        assert_eq!(
+            ("\u{201c}".to_string(), Encoding::Cp1252, false),
+            decoder.decode(b"\x93", Some(Path::new("b,v"))).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_rcs() {
+        let mut rcs = parse_rcs_bytes(&latin1_file()).unwrap();
+        let report = Decoder::default().decode_rcs(&mut rcs, None).unwrap();
+        assert_eq!(1, report.len());
+        assert_eq!(Encoding::Cp1252, report[0].encoding);
+        let num = report[0].revision.clone().unwrap();
+        assert_eq!(
+            "Fixed the \u{201c}Named\u{201d} line\n",
+            rcs.deltas[&num].log
+        );
+
+        let mut rcs = parse_rcs_bytes(&latin1_file()).unwrap();
+        let result = Decoder::new(&[Encoding::Utf8]).decode_rcs(&mut rcs, None);
+        assert!(matches!(result, Err(Error::Decode(Some(_)))));
+    }
+
+    #[test]
+    fn decode_binary_rcs() {
+        let mut contents = latin1_file();
+        let comment = b"comment\t@# @;\n";
+        let pos = contents
+            .windows(comment.len())
+            .position(|w| w == comment)
+            .unwrap();
+        contents.splice(
+            pos + comment.len()..pos + comment.len(),
+            b"expand\t@b@;\n".iter().cloned(),
+        );
+        let original = parse_rcs_bytes(&contents).unwrap();
+        let mut rcs = original.clone();
+        let report = Decoder::default().decode_rcs(&mut rcs, None).unwrap();
+        assert!(rcs.is_binary());
+        let num = report[0].revision.clone().unwrap();
+        assert_eq!(
+            "Fixed the \u{201c}Named\u{201d} line\n",
+            rcs.deltas[&num].log
+        );
+        assert!(Decoder::default()
+            .decode_rcs(&mut rcs, None)
+            .unwrap()
+            .is_empty());
+
+        let mut written = parse_rcs_bytes(&rcs.to_bytes()).unwrap();
+        assert!(Decoder::default()
+            .decode_rcs(&mut written, None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(rcs, written);
+        for num in original.deltas.keys() {
+            assert_eq!(
+                original.checkout_bytes(num).unwrap(),
+                written.checkout_bytes(num).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn report_repository() {
+        let dir = broken_repository("encoding", "text1.txt,v");
+        std::fs::write(dir.join("latin1.txt,v"), latin1_file()).unwrap();
+        let (report, failed) = Decoder::default().report_repository(&dir).unwrap();
+        assert_eq!(1, report.len());
+        assert!(report[0].0.ends_with("latin1.txt,v"));
+        assert_eq!(1, failed.len());
+        assert!(matches!(&failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
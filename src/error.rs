@@ -0,0 +1,125 @@
+use crate::Num;
+use nom::error::{VerboseError, VerboseErrorKind};
+use std::{fmt, path::PathBuf};
+
+/// Errors reported by the api.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// The input is not a valid comma-v file. The message tells the failing rule and the input where it failed.
+    Parse(String),
+    /// The revision does not exist in the file.
+    UnknownRevision(Num),
+    /// The symbolic name does not exist in the file.
+    UnknownSymbol(String),
+    /// The symbolic name already exists.
+    SymbolExists(String),
+    /// The symbolic name is not valid: it must consist of idchars and contain a non-digit.
+    InvalidSymbol(String),
+    /// The identifier is not valid as a state or a login: it must consist of idchars and dots.
+    InvalidId(String),
+    /// The diff of the revision cannot be applied to the text of its parent.
+    InvalidDiff(Num),
+    /// A text cannot be decoded with the given encodings. The revision is `None` for the description.
+    Decode(Option<Num>),
+    /// The revision is locked by another user.
+    Locked(Num, String),
+    /// The revision is not locked.
+    NotLocked(Num),
+    /// The user holds several locks, so the revision must be given.
+    MultipleLocks(String),
+    /// The user is not on the access list of the file.
+    AccessDenied(String),
+    /// The revision cannot be removed. The message tells the reason.
+    CannotOutdate(Num, String),
+    /// The lock file of a comma-v file exists: somebody else is changing the file.
+    LockFileExists(PathBuf),
+    /// The content to check in is the same as the revision it is based on.
+    Unchanged(Num),
+    /// Processing a file of a repository failed.
+    InFile(PathBuf, Box<Error>),
+}
+
+impl Error {
+    /// Converts a nom error to a readable parse error.
+    pub(crate) fn from_nom(err: nom::Err<VerboseError<&str>>) -> Error {
+        match err {
+            nom::Err::Incomplete(_) => Error::Parse("unexpected end of input".to_string()),
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                let rules: Vec<&str> = e
+                    .errors
+                    .iter()
+                    .filter_map(|(_, kind)| match kind {
+                        VerboseErrorKind::Context(ctx) => Some(*ctx),
+                        _ => None,
+                    })
+                    .collect();
+                let near = e
+                    .errors
+                    .first()
+                    .map(|(input, _)| input.lines().next().unwrap_or(""))
+                    .unwrap_or("");
+                Error::Parse(format!("{} near '{}'", rules.join(" < "), near))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Parse(msg) => write!(f, "parse error: {}", msg),
+            Error::UnknownRevision(num) => write!(f, "revision {} is not found", num),
+            Error::UnknownSymbol(name) => write!(f, "symbolic name {} is not found", name),
+            Error::SymbolExists(name) => write!(f, "symbolic name {} already exists", name),
+            Error::InvalidSymbol(name) => write!(f, "invalid symbolic name '{}'", name),
+            Error::InvalidId(id) => write!(f, "invalid identifier '{}'", id),
+            Error::InvalidDiff(num) => write!(f, "diff of revision {} cannot be applied", num),
+            Error::Decode(Some(num)) => write!(f, "revision {} cannot be decoded", num),
+            Error::Decode(None) => write!(f, "text cannot be decoded"),
+            Error::Locked(num, locker) => write!(f, "revision {} is locked by {}", num, locker),
+            Error::NotLocked(num) => write!(f, "revision {} is not locked", num),
+            Error::MultipleLocks(user) => {
+                write!(f, "{} holds several locks, a revision must be given", user)
+            }
+            Error::AccessDenied(user) => write!(f, "user {} is not on the access list", user),
+            Error::CannotOutdate(num, reason) => {
+                write!(f, "revision {} cannot be outdated: {}", num, reason)
+            }
+            Error::LockFileExists(lock) => {
+                write!(f, "lock file {} exists, the file is busy", lock.display())
+            }
+            Error::Unchanged(num) => write!(f, "content is unchanged from revision {}", num),
+            Error::InFile(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::InFile(_, e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parsers::parse_num;
+
+    #[test]
+    fn from_nom() {
+        let err = super::Error::from_nom(parse_num("x.1\nsecond line").unwrap_err());
+        assert_eq!("parse error: Num near 'x.1'", err.to_string());
+    }
+}
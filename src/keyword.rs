@@ -0,0 +1,378 @@
+use crate::{date::format_date, *};
+use std::path::Path;
+
+/// Keyword substitution modes of RCS, see `-k` option of `co`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Expand {
+    /// `kv`: `$Revision: 1.2 $`, the default mode.
+    KeyValue,
+    /// `kvl`: like `kv`, and the locker is always inserted into `$Id$` and `$Header$`.
+    KeyValueLocker,
+    /// `k`: `$Revision$`, values are removed.
+    Key,
+    /// `o`: the old text is kept as it was checked in.
+    Old,
+    /// `b`: binary file, like `o`, and the content is handled as bytes.
+    Binary,
+    /// `v`: `1.2`, only the value is written.
+    Value,
+}
+
+impl Expand {
+    /// Parses the value of the `expand` admin field or of the `-k` option.
+    ///
+    /// ```rust
+    /// use rcs_parser::Expand;
+    /// assert_eq!(Some(Expand::Binary), Expand::from_option("b"));
+    /// assert_eq!(None, Expand::from_option("x"));
+    /// ```
+    pub fn from_option(option: &str) -> Option<Expand> {
+        match option {
+            "kv" => Some(Expand::KeyValue),
+            "kvl" => Some(Expand::KeyValueLocker),
+            "k" => Some(Expand::Key),
+            "o" => Some(Expand::Old),
+            "b" => Some(Expand::Binary),
+            "v" => Some(Expand::Value),
+            _ => None,
+        }
+    }
+
+    /// Returns the mode as it is written in the `expand` admin field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Expand::KeyValue => "kv",
+            Expand::KeyValueLocker => "kvl",
+            Expand::Key => "k",
+            Expand::Old => "o",
+            Expand::Binary => "b",
+            Expand::Value => "v",
+        }
+    }
+}
+
+/// Keywords substituted by RCS.
+pub static KEYWORDS: [&str; 11] = [
+    "Author", "Date", "Header", "Id", "Locker", "Log", "Name", "RCSfile", "Revision", "Source",
+    "State",
+];
+
+impl RcsData {
+    /// Returns the keyword substitution mode of the file. Default is `kv`.
+    pub fn expand_mode(&self) -> Expand {
+        self.expand
+            .as_deref()
+            .and_then(Expand::from_option)
+            .unwrap_or(Expand::KeyValue)
+    }
+
+    /// Shows if the file is binary, i.e. it is stored with `-kb`.
+    pub fn is_binary(&self) -> bool {
+        self.expand_mode() == Expand::Binary
+    }
+
+    /// Returns the value of a keyword for a revision, as `co` would insert it.
+    ///
+    /// `name` is the symbolic name the revision is checked out by, used by `$Name$`.
+    /// `rcsfile` is the path of the comma-v file, used by `$RCSfile$`, `$Source$`, `$Id$` and `$Header$`.
+    /// The value of `$Log$` is the file name, the log message is inserted by [expand_keywords](RcsData::expand_keywords).
+    pub fn keyword_value(
+        &self,
+        keyword: &str,
+        rev: &Num,
+        name: Option<&str>,
+        rcsfile: &Path,
+        mode: Expand,
+    ) -> Result<Option<String>, Error> {
+        let delta = self.get_delta(rev)?;
+        let filename = rcsfile
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let locker = self
+            .locks
+            .iter()
+            .find(|(_, num)| num == rev)
+            .map(|(id, _)| id.clone());
+        let state = delta.state.clone().unwrap_or_default();
+        let header = |file: String| {
+            let mut value = format!(
+                "{} {} {} {} {}",
+                file,
+                rev,
+                format_date(&delta.date),
+                delta.author,
+                state
+            );
+            if let (Some(locker), Expand::KeyValueLocker) = (&locker, mode) {
+                value.push(' ');
+                value.push_str(locker);
+            }
+            value
+        };
+        Ok(match keyword {
+            "Author" => Some(delta.author.clone()),
+            "Date" => Some(format_date(&delta.date)),
+            "Header" => Some(header(rcsfile.to_string_lossy().into_owned())),
+            "Id" => Some(header(filename)),
+            "Locker" => Some(locker.unwrap_or_default()),
+            "Log" | "RCSfile" => Some(filename),
+            "Name" => Some(name.unwrap_or_default().to_string()),
+            "Revision" => Some(rev.to_string()),
+            "Source" => Some(rcsfile.to_string_lossy().into_owned()),
+            "State" => Some(state),
+            _ => None,
+        })
+    }
+
+    /// Substitutes keywords in the text of a revision.
+    ///
+    /// Nothing is changed for `-ko` and `-kb`. Except for `-kk`, the log message of the revision is inserted
+    /// after the line of `$Log$`, as `co` does: a `Revision` line with the date and the author, then the lines
+    /// of the log. Each line is prefixed by the text before `$Log$` on its line, or by the comment leader of
+    /// the file if there is no such text.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num, Expand};
+    /// use std::path::Path;
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let text = rcs
+    ///     .expand_keywords("$Name$\n * $Log$\n", &num![2, 1], Some("v2_1"), Path::new("a,v"), Expand::KeyValue)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     "$Name: v2_1 $\n * $Log: a,v $\n * Revision 2.1  2021/04/10 09:38:42  dseres\n * lao back\n *\n",
+    ///     text
+    /// );
+    /// ```
+    pub fn expand_keywords(
+        &self,
+        text: &str,
+        rev: &Num,
+        name: Option<&str>,
+        rcsfile: &Path,
+        mode: Expand,
+    ) -> Result<String, Error> {
+        if mode == Expand::Old || mode == Expand::Binary {
+            return Ok(text.to_string());
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match find_keyword(rest) {
+                Some((keyword, end)) => {
+                    let value = self
+                        .keyword_value(keyword, rev, name, rcsfile, mode)?
+                        .unwrap_or_default();
+                    let leader = result.rfind('\n').map_or(0, |n| n + 1)..result.len();
+                    match mode {
+                        Expand::Key => result.push_str(&format!("${}$", keyword)),
+                        Expand::Value => result.push_str(&value),
+                        _ => result.push_str(&format!("${}: {} $", keyword, value)),
+                    }
+                    rest = &rest[end..];
+                    if keyword == "Log" && mode != Expand::Key {
+                        let line_end = rest.find('\n').map_or(rest.len(), |n| n + 1);
+                        result.push_str(&rest[..line_end]);
+                        if !result.ends_with('\n') {
+                            result.push('\n');
+                        }
+                        rest = &rest[line_end..];
+                        let leader = result[leader].to_string();
+                        self.insert_log(&mut result, rev, &leader)?;
+                    }
+                }
+                None => {
+                    result.push('$');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Inserts the log message of a revision as `co` does for `$Log$`, each line prefixed by `leader`.
+    fn insert_log(&self, result: &mut String, rev: &Num, leader: &str) -> Result<(), Error> {
+        let delta = self.get_delta(rev)?;
+        let leader = if leader.is_empty() {
+            self.comment.as_deref().unwrap_or_default()
+        } else {
+            leader
+        };
+        result.push_str(&format!(
+            "{}Revision {}  {}  {}\n",
+            leader,
+            rev,
+            format_date(&delta.date),
+            delta.author
+        ));
+        for line in delta.log.split('\n') {
+            if line.is_empty() {
+                result.push_str(leader.trim_end());
+            } else {
+                result.push_str(leader);
+                result.push_str(line);
+            }
+            result.push('\n');
+        }
+        Ok(())
+    }
+
+    /// Checks out a revision like `co` does: keywords are substituted according to `mode`,
+    /// or to the mode of the file if `mode` is `None`. `name` is the symbolic name the revision is
+    /// checked out by, if any, see [keyword_value](RcsData::keyword_value).
+    ///
+    /// The content is returned as bytes, so binary files are reconstructed exactly.
+    pub fn checkout_expanded(
+        &self,
+        rev: &Num,
+        name: Option<&str>,
+        rcsfile: &Path,
+        mode: Option<Expand>,
+    ) -> Result<Vec<u8>, Error> {
+        let mode = mode.unwrap_or_else(|| self.expand_mode());
+        let text = self.checkout(rev)?;
+        let text = if self.is_binary() {
+            text
+        } else {
+            self.expand_keywords(&text, rev, name, rcsfile, mode)?
+        };
+        Ok(text_to_bytes(&text))
+    }
+}
+
+/// Finds a keyword at the beginning of `text`, which starts with `$`.
+/// Returns the keyword and the position after its closing `$`.
+fn find_keyword(text: &str) -> Option<(&str, usize)> {
+    let name_end = text[1..]
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .map(|n| n + 1)?;
+    let keyword = KEYWORDS.iter().find(|k| **k == &text[1..name_end])?;
+    match text[name_end..].chars().next()? {
+        '$' => Some((keyword, name_end + 1)),
+        ':' => {
+            let value_end = text[name_end..].find(['$', '\n'])? + name_end;
+            if &text[value_end..value_end + 1] == "$" {
+                Some((keyword, value_end + 1))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+    use std::path::Path;
+
+    #[test]
+    fn find_keyword() {
+        assert_eq!(Some(("Id", 4)), super::find_keyword("$Id$ x"));
+        assert_eq!(
+            Some(("Revision", 16)),
+            super::find_keyword("$Revision: 1.1 $")
+        );
+        assert_eq!(None, super::find_keyword("$Revision: 1.1\n$"));
+        assert_eq!(None, super::find_keyword("$Unknown$"));
+        assert_eq!(None, super::find_keyword("$Id"));
+    }
+
+    #[test]
+    fn expand_keywords() {
+        let rcs = parse_example("text1.txt,v");
+        let file = Path::new("/repo/text1.txt,v");
+        let text = "$Id$ $Revision: 1.1 $ $Author$ $ $Date\n";
+        assert_eq!(
+            "$Id: text1.txt,v 1.2 2021/03/25 10:16:43 dseres beta $ $Revision: 1.2 $ $Author: dseres $ $ $Date\n",
+            rcs.expand_keywords(text, &num![1, 2], None, file, Expand::KeyValue)
+                .unwrap()
+        );
+        assert_eq!(
+            "$Header: /repo/text1.txt,v 2.1 2021/04/10 09:38:42 dseres Production dseres $\n",
+            rcs.expand_keywords(
+                "$Header$\n",
+                &num![2, 1],
+                None,
+                file,
+                Expand::KeyValueLocker
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "$Id$ $Revision$ $Author$ $ $Date\n",
+            rcs.expand_keywords(text, &num![1, 2], None, file, Expand::Key)
+                .unwrap()
+        );
+        assert_eq!(
+            "1.2 dseres",
+            rcs.expand_keywords(
+                "$Revision$ $Author$",
+                &num![1, 2],
+                None,
+                file,
+                Expand::Value
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            text,
+            rcs.expand_keywords(text, &num![1, 2], None, file, Expand::Binary)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn expand_log_and_name() {
+        let rcs = parse_example("text1.txt,v");
+        let file = Path::new("/repo/text1.txt,v");
+        assert_eq!(
+            "/* $Log: text1.txt,v $ */\n\
+             /* Revision 1.2  2021/03/25 10:16:43  dseres\n\
+             /* Tzu has given some new idea. \n\
+             /*\n\
+             /* Maybe it is a @useful@ idea.\n\
+             /*\n\
+             end\n",
+            rcs.expand_keywords(
+                "/* $Log$ */\nend\n",
+                &num![1, 2],
+                None,
+                file,
+                Expand::KeyValue
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "$Log: text1.txt,v $\n# Revision 2.1  2021/04/10 09:38:42  dseres\n# lao back\n#\n",
+            rcs.expand_keywords("$Log$", &num![2, 1], None, file, Expand::KeyValue)
+                .unwrap()
+        );
+        assert_eq!(
+            "$Log$\n",
+            rcs.expand_keywords("$Log$\n", &num![2, 1], None, file, Expand::Key)
+                .unwrap()
+        );
+        assert_eq!(
+            "$Name: v2_1 $",
+            rcs.expand_keywords("$Name$", &num![2, 1], Some("v2_1"), file, Expand::KeyValue)
+                .unwrap()
+        );
+        assert_eq!(
+            "v2_1",
+            rcs.expand_keywords("$Name$", &num![2, 1], Some("v2_1"), file, Expand::Value)
+                .unwrap()
+        );
+        assert_eq!(
+            "$Name:  $",
+            rcs.expand_keywords("$Name$", &num![2, 1], None, file, Expand::KeyValue)
+                .unwrap()
+        );
+    }
+}
@@ -19,7 +19,87 @@
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 mod parsers;
-pub use parsers::parse_rcs;
+pub use parsers::{parse_rcs, parse_rcs_with_visitor, DeltaText};
+
+mod error;
+pub use error::Error;
+
+mod stream;
+pub use stream::{Event, RcsReader};
+
+mod visitor;
+pub use visitor::Visitor;
+
+mod checkout;
+pub use checkout::split_lines;
+
+mod date;
+pub use date::parse_date;
+
+mod binary;
+pub use binary::{parse_rcs_bytes, text_to_bytes};
+
+mod keyword;
+pub use keyword::{Expand, KEYWORDS};
+
+mod vendor;
+
+mod symbols;
+pub use symbols::SymbolKind;
+
+mod lifetime;
+pub use lifetime::{Interval, DEAD};
+
+mod repository;
+pub use repository::{find_rcs_files, lock_file_path, read_rcs_file, working_path, write_rcs_file};
+
+mod snapshot;
+pub use snapshot::{snapshot, Selection, Snapshot};
+
+mod textdiff;
+
+mod merge;
+pub use merge::{merge3, Merge};
+
+mod rcsdiff;
+pub use rcsdiff::{diff_revisions, diff_working, UnifiedDiff};
+
+mod stats;
+pub use stats::{repository_churn, Churn, LineStats};
+
+mod rlog;
+pub use rlog::RevisionFilter;
+
+mod authors;
+pub use authors::{AuthorMap, Identity};
+
+mod encoding;
+pub use encoding::{Decoder, Encoding, Recoded};
+
+mod svn;
+pub use svn::SvnExport;
+
+mod locks;
+pub use locks::{lock_report, LockInfo};
+
+mod outdate;
+
+mod writer;
+
+mod metadata;
+
+mod access;
+
+mod checkin;
+pub use checkin::CheckIn;
+
+mod rehead;
+
+mod cache;
+pub use cache::CheckoutCache;
+
+mod revisions;
+pub use revisions::Revisions;
 
 #[macro_use]
 mod num;
@@ -30,7 +110,7 @@ pub use num::Num;
 pub enum DiffCommand {
     ///For the deltatext of head, the enum contains the initial lines.
     Head(Vec<String>),
-    ///This instruction means add the lines at position
+    ///This instruction means add the lines after position. Each line keeps its terminator ("\n", "\r\n" or "\r"), the last line of a file may have none.
     Add(u32, Vec<String>),
     ///This instruction means delete n (second parameter) lines from position (first parameter).
     Delete(u32, u32),
@@ -49,7 +129,6 @@ pub fn is_empty(t: &Text) -> bool {
     }
 }
 
-
 #[derive(Debug, PartialEq, Clone)]
 pub struct Delta {
     pub num: Num,
@@ -86,6 +165,34 @@ pub struct RcsData {
     pub deltas: std::collections::BTreeMap<Num, Delta>,
 }
 
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use crate::*;
+
+    /// Parses a file of the `examples` directory.
+    pub fn parse_example(file: &str) -> RcsData {
+        let contents = std::fs::read_to_string(format!("examples/{}", file)).unwrap();
+        parse_rcs(contents.as_str()).unwrap().1
+    }
+
+    /// Creates a repository in the temporary directory with a copy of an example file and a broken file `broken.c,v`.
+    pub fn broken_repository(name: &str, example: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rcs-parser-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(format!("examples/{}", example), dir.join(example)).unwrap();
+        std::fs::write(dir.join("broken.c,v"), "head\t1.1;\ngarbage").unwrap();
+        dir
+    }
+
+    /// Returns the content of each revision.
+    pub fn contents(rcs: &RcsData) -> Vec<(Num, String)> {
+        rcs.deltas
+            .keys()
+            .map(|num| (num.clone(), rcs.checkout(num).unwrap()))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,157 @@
+use crate::*;
+
+/// State of removed revisions in CVS repositories.
+pub static DEAD: &str = "dead";
+
+/// A period while a file exists on a branch.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Interval {
+    /// The revision which added or resurrected the file. On a branch it can be the branching point too.
+    pub added: Num,
+    /// Date of the revision which added the file.
+    pub added_date: Num,
+    /// The revision which removed the file, `None` if the file still exists.
+    pub removed: Option<Num>,
+    /// Date of the revision which removed the file.
+    pub removed_date: Option<Num>,
+}
+
+impl RcsData {
+    /// Shows if the revision is removed, i.e. its state is `dead`.
+    pub fn is_dead(&self, rev: &Num) -> bool {
+        self.deltas
+            .get(rev)
+            .and_then(|d| d.state.as_deref())
+            .is_some_and(|s| s == DEAD)
+    }
+
+    /// Returns the periods while the file exists on a branch. An empty num means the trunk.
+    ///
+    /// A branch inherits the file if its branching point is alive. Dead revisions remove the file,
+    /// and a later alive revision resurrects it.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/removed.c,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let trunk = rcs.lifetime(&num![]).unwrap();
+    /// assert_eq!(1, trunk.len());
+    /// assert_eq!(num![1, 2], trunk[0].added);
+    /// assert_eq!(Some(num![1, 3]), trunk[0].removed);
+    /// ```
+    pub fn lifetime(&self, branch: &Num) -> Result<Vec<Interval>, Error> {
+        let mut intervals = Vec::new();
+        let mut current: Option<Interval> = None;
+        if branch.numbers.len() > 1 {
+            let point = branch.get_branching_point();
+            if !self.is_dead(&point) {
+                current = Some(Interval {
+                    added: point.clone(),
+                    added_date: self.get_delta(&point)?.date.clone(),
+                    removed: None,
+                    removed_date: None,
+                });
+            }
+        }
+        for num in self.branch_revisions(branch)? {
+            let date = &self.get_delta(&num)?.date;
+            match (current.take(), self.is_dead(&num)) {
+                (Some(mut interval), true) => {
+                    interval.removed = Some(num);
+                    interval.removed_date = Some(date.clone());
+                    intervals.push(interval);
+                }
+                (Some(interval), false) => current = Some(interval),
+                (None, false) => {
+                    current = Some(Interval {
+                        added: num,
+                        added_date: date.clone(),
+                        removed: None,
+                        removed_date: None,
+                    })
+                }
+                (None, true) => {}
+            }
+        }
+        intervals.extend(current);
+        Ok(intervals)
+    }
+
+    /// Returns the lifetime of the file on the trunk and on each branch.
+    /// The trunk is given by an empty num.
+    pub fn lifetimes(&self) -> Result<Vec<(Num, Vec<Interval>)>, Error> {
+        let mut result = vec![(num![], self.lifetime(&num![])?)];
+        for branch in self.branches() {
+            let intervals = self.lifetime(&branch)?;
+            result.push((branch, intervals));
+        }
+        Ok(result)
+    }
+
+    /// Detects the pattern created by `cvs add` on a branch: revision `1.1` is dead on the trunk,
+    /// and the file is added on a branch of it. The branch is returned.
+    pub fn added_on_branch(&self) -> Option<Num> {
+        let first = num![1, 1];
+        if !self.is_dead(&first) {
+            return None;
+        }
+        self.deltas
+            .get(&first)?
+            .branches
+            .iter()
+            .find(|b| !self.is_dead(b))
+            .map(Num::get_branch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn lifetime() {
+        let rcs = parse_example("removed.c,v");
+        let branch = rcs.lifetime(&num![1, 1, 2]).unwrap();
+        assert_eq!(
+            vec![
+                Interval {
+                    added: num![1, 1, 2, 1],
+                    added_date: num![2021, 1, 1, 12, 0, 0],
+                    removed: Some(num![1, 1, 2, 2]),
+                    removed_date: Some(num![2021, 2, 1, 12, 0, 0]),
+                },
+                Interval {
+                    added: num![1, 1, 2, 3],
+                    added_date: num![2021, 3, 1, 12, 0, 0],
+                    removed: None,
+                    removed_date: None,
+                }
+            ],
+            branch
+        );
+    }
+
+    #[test]
+    fn lifetimes() {
+        let rcs = parse_example("text1.txt,v");
+        let lifetimes = rcs.lifetimes().unwrap();
+        assert_eq!(3, lifetimes.len());
+        assert_eq!(num![1, 2, 1], lifetimes[1].0);
+        assert_eq!(num![1, 2], lifetimes[1].1[0].added);
+        assert!(lifetimes
+            .iter()
+            .all(|(_, i)| i.len() == 1 && i[0].removed.is_none()));
+    }
+
+    #[test]
+    fn added_on_branch() {
+        assert_eq!(
+            Some(num![1, 1, 2]),
+            parse_example("removed.c,v").added_on_branch()
+        );
+        let rcs = parse_example("text1.txt,v");
+        assert_eq!(None, rcs.added_on_branch());
+    }
+}
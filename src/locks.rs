@@ -0,0 +1,221 @@
+use crate::date::{date_fields, unix_seconds};
+use crate::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A lock found in a repository.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LockInfo {
+    /// The comma-v file.
+    pub path: PathBuf,
+    /// The login of the user holding the lock.
+    pub locker: String,
+    /// The locked revision.
+    pub revision: Num,
+    /// The date of the locked revision.
+    pub date: Num,
+    /// The time elapsed since the date of the locked revision.
+    pub age: Duration,
+}
+
+impl RcsData {
+    /// Returns the user who locked a revision.
+    pub fn locker(&self, rev: &Num) -> Option<&str> {
+        self.locks
+            .iter()
+            .find(|(_, num)| num == rev)
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// Resolves the revision of a lock operation. An empty revision means the newest revision of the default branch.
+    fn lock_target(&self, rev: &str) -> Result<Num, Error> {
+        if rev.is_empty() {
+            self.default_head()
+        } else {
+            self.resolve_revision(rev)
+        }
+    }
+
+    /// Locks a revision for a user, like `rcs -lrev`. Returns the locked revision.
+    ///
+    /// The revision is given by a number or by a symbolic name, a branch means its newest revision.
+    /// An empty revision means the newest revision of the default branch. Locking a revision again by the
+    /// same user does nothing, a revision locked by someone else gives [Error::Locked].
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(num![1, 2, 2, 3], rcs.lock("Fix2", "jdoe").unwrap());
+    /// assert_eq!(Some("jdoe"), rcs.locker(&num![1, 2, 2, 3]));
+    /// assert!(rcs.lock("2.1", "jdoe").is_err());
+    /// ```
+    pub fn lock(&mut self, rev: &str, user: &str) -> Result<Num, Error> {
+        let num = self.lock_target(rev)?;
+        match self.locker(&num) {
+            Some(locker) if locker == user => {}
+            Some(locker) => return Err(Error::Locked(num, locker.to_string())),
+            None => self.locks.insert(0, (user.to_string(), num.clone())),
+        }
+        Ok(num)
+    }
+
+    /// Unlocks a revision locked by the user, like `rcs -urev`. Returns the unlocked revision.
+    ///
+    /// An empty revision means the revision locked by the user, if there is only one. A revision locked by
+    /// someone else gives [Error::Locked], see [break_lock](RcsData::break_lock).
+    pub fn unlock(&mut self, rev: &str, user: &str) -> Result<Num, Error> {
+        let num = if rev.is_empty() {
+            let mut locked = self.locks.iter().filter(|(id, _)| id == user);
+            match (locked.next(), locked.next()) {
+                (Some((_, num)), None) => num.clone(),
+                (Some(_), Some(_)) => return Err(Error::MultipleLocks(user.to_string())),
+                (None, _) => return Err(Error::NotLocked(self.default_head()?)),
+            }
+        } else {
+            self.lock_target(rev)?
+        };
+        match self.locker(&num) {
+            Some(locker) if locker == user => {
+                self.locks.retain(|(_, n)| *n != num);
+                Ok(num)
+            }
+            Some(locker) => Err(Error::Locked(num, locker.to_string())),
+            None => Err(Error::NotLocked(num)),
+        }
+    }
+
+    /// Breaks the lock of a revision, whoever holds it. Returns the user who held the lock.
+    pub fn break_lock(&mut self, rev: &str) -> Result<String, Error> {
+        let num = self.lock_target(rev)?;
+        let locker = self
+            .locker(&num)
+            .ok_or_else(|| Error::NotLocked(num.clone()))?
+            .to_string();
+        self.locks.retain(|(_, n)| *n != num);
+        Ok(locker)
+    }
+
+    /// Turns strict locking on or off, like `rcs -L` and `rcs -U`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns the locks of the file with their ages at `now`.
+    pub fn lock_infos(&self, path: &Path, now: SystemTime) -> Result<Vec<LockInfo>, Error> {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let mut infos = Vec::new();
+        for (locker, num) in self.locks.iter() {
+            let date = self.get_delta(num)?.date.clone();
+            let age = (now - unix_seconds(&date_fields(&date))).max(0) as u64;
+            infos.push(LockInfo {
+                path: path.to_path_buf(),
+                locker: locker.clone(),
+                revision: num.clone(),
+                date,
+                age: Duration::from_secs(age),
+            });
+        }
+        Ok(infos)
+    }
+}
+
+/// Lists the locks of all comma-v files in a repository, the oldest first. Ages are computed at `now`.
+///
+/// A comma-v file which cannot be read does not stop the report: the locks are returned together with the
+/// errors of such files, each error is an [Error::InFile]. Errors of listing the repository are returned.
+///
+/// ```rust
+/// use rcs_parser::lock_report;
+/// use std::{path::Path, time::SystemTime};
+///
+/// let (report, failed) = lock_report(Path::new("examples"), SystemTime::now()).unwrap();
+/// assert_eq!("dseres", report[0].locker);
+/// assert!(failed.is_empty());
+/// ```
+pub fn lock_report(
+    repository: &Path,
+    now: SystemTime,
+) -> Result<(Vec<LockInfo>, Vec<Error>), Error> {
+    let mut report = Vec::new();
+    let mut failed = Vec::new();
+    for path in find_rcs_files(repository)? {
+        let infos = read_rcs_file(&path).and_then(|rcs| {
+            rcs.lock_infos(&path, now)
+                .map_err(|e| Error::InFile(path.clone(), Box::new(e)))
+        });
+        match infos {
+            Ok(infos) => report.extend(infos),
+            Err(e) => failed.push(e),
+        }
+    }
+    report.sort_by_key(|info| std::cmp::Reverse(info.age));
+    Ok((report, failed))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{broken_repository, parse_example};
+    use crate::*;
+    use std::path::Path;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn lock_unlock() {
+        let mut rcs = parse_example("text1.txt,v");
+        assert_eq!(num![2, 1], rcs.lock("", "dseres").unwrap());
+        assert_eq!(1, rcs.locks.len());
+        assert!(matches!(
+            rcs.unlock("2.1", "jdoe"),
+            Err(Error::Locked(_, locker)) if locker == "dseres"
+        ));
+        assert_eq!(num![2, 1], rcs.unlock("", "dseres").unwrap());
+        assert!(rcs.locks.is_empty());
+        assert!(matches!(
+            rcs.unlock("2.1", "dseres"),
+            Err(Error::NotLocked(_))
+        ));
+        rcs.lock("1.1", "jdoe").unwrap();
+        rcs.lock("1.2", "jdoe").unwrap();
+        assert!(matches!(
+            rcs.unlock("", "jdoe"),
+            Err(Error::MultipleLocks(_))
+        ));
+        assert_eq!(num![1, 1], rcs.unlock("1.1", "jdoe").unwrap());
+        assert!(rcs.lock("1.9", "jdoe").is_err());
+    }
+
+    #[test]
+    fn break_lock() {
+        let mut rcs = parse_example("text1.txt,v");
+        assert_eq!("dseres", rcs.break_lock("2.1").unwrap());
+        assert!(matches!(rcs.break_lock("2.1"), Err(Error::NotLocked(_))));
+        rcs.set_strict(false);
+        assert!(!rcs.strict);
+    }
+
+    #[test]
+    fn lock_infos() {
+        let rcs = parse_example("text1.txt,v");
+        let now = UNIX_EPOCH + Duration::from_secs(1618047522 + 3600);
+        let infos = rcs.lock_infos(Path::new("text1.txt,v"), now).unwrap();
+        assert_eq!(1, infos.len());
+        assert_eq!(num![2, 1], infos[0].revision);
+        assert_eq!(Duration::from_secs(3600), infos[0].age);
+    }
+
+    #[test]
+    fn lock_report() {
+        let dir = broken_repository("locks", "text1.txt,v");
+        let (report, failed) = super::lock_report(&dir, UNIX_EPOCH).unwrap();
+        assert_eq!(1, report.len());
+        assert_eq!("dseres", report[0].locker);
+        assert_eq!(1, failed.len());
+        assert!(matches!(&failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,2 +1,145 @@
+use rcs_parser::*;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: rcs-parser <command> [options]
+
+commands:
+    diff [-u | -c] [-U lines] [-rrev1 [-rrev2]] file,v [working-file]
+        compares two revisions, or a revision with the working file
+    authors [-Amap] repository
+        lists the logins which are not in the author map, with their number of revisions";
+
 #[cfg(not(tarpaulin_include))]
-fn main() {}
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("diff") => diff(&args[1..]),
+        Some("authors") => authors(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("rcs-parser: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns the value of an option, which is either attached (`-r1.2`) or the next argument (`-r 1.2`).
+#[cfg(not(tarpaulin_include))]
+fn option_value<'a>(
+    arg: &'a str,
+    option: &str,
+    rest: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a str, Error> {
+    match &arg[option.len()..] {
+        "" => rest
+            .next()
+            .map(String::as_str)
+            .ok_or_else(|| Error::Parse(format!("missing value of {}", option))),
+        value => Ok(value),
+    }
+}
+
+/// `rcsdiff`: exits with 0 if there are no differences, with 1 otherwise.
+#[cfg(not(tarpaulin_include))]
+fn diff(args: &[String]) -> Result<i32, Error> {
+    let mut context_format = false;
+    let mut lines = 3;
+    let mut revisions = Vec::new();
+    let mut files = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-u" => context_format = false,
+            "-c" => context_format = true,
+            a if a.starts_with("-U") => {
+                lines = option_value(a, "-U", &mut rest)?
+                    .parse()
+                    .map_err(|_| Error::Parse(format!("invalid number of lines in {}", a)))?
+            }
+            a if a.starts_with("-r") => revisions.push(option_value(a, "-r", &mut rest)?),
+            a if a.starts_with('-') => return Err(Error::Parse(format!("unknown option {}", a))),
+            a => files.push(PathBuf::from(a)),
+        }
+    }
+    let rcsfile = files
+        .first()
+        .ok_or_else(|| Error::Parse("missing comma-v file".to_string()))?;
+    let rcs = read_rcs_file(rcsfile)?;
+    let diff = match revisions.as_slice() {
+        [rev1, rev2] => {
+            let mut diff = diff_revisions(
+                &rcs,
+                &rcs.resolve_revision(rev1)?,
+                &rcs.resolve_revision(rev2)?,
+                rcsfile,
+            )?;
+            diff.old_label = format!("{} {}", rcsfile.display(), diff.old_label);
+            diff.new_label = format!("{} {}", rcsfile.display(), diff.new_label);
+            diff
+        }
+        [] | [_] => {
+            let rev = match revisions.first() {
+                Some(rev) => rcs.resolve_revision(rev)?,
+                None => rcs.default_head()?,
+            };
+            let working = files
+                .get(1)
+                .cloned()
+                .unwrap_or_else(|| working_path(rcsfile));
+            diff_working(&rcs, &rev, rcsfile, &working)?
+        }
+        _ => {
+            return Err(Error::Parse(
+                "at most two revisions can be given".to_string(),
+            ))
+        }
+    };
+    if diff.is_empty() {
+        return Ok(0);
+    }
+    if context_format {
+        print!("{}", diff.context(lines));
+    } else {
+        print!("{}", diff.unified(lines));
+    }
+    Ok(1)
+}
+
+/// Reports the unmapped logins of a repository: exits with 0 if all logins are mapped, with 1 otherwise,
+/// and with 2 if a comma-v file cannot be read.
+#[cfg(not(tarpaulin_include))]
+fn authors(args: &[String]) -> Result<i32, Error> {
+    let mut map = AuthorMap::default();
+    let mut repository = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            a if a.starts_with("-A") => {
+                map = AuthorMap::read(option_value(a, "-A", &mut rest)?.as_ref())?
+            }
+            a if a.starts_with('-') => return Err(Error::Parse(format!("unknown option {}", a))),
+            a => repository = Some(PathBuf::from(a)),
+        }
+    }
+    let repository = repository.ok_or_else(|| Error::Parse("missing repository".to_string()))?;
+    let (unmapped, failed) = map.unmapped_in_repository(&repository)?;
+    for (login, count) in unmapped.iter() {
+        println!("{}\t{}", login, count);
+    }
+    for e in failed.iter() {
+        eprintln!("rcs-parser: {}", e);
+    }
+    Ok(if !failed.is_empty() {
+        2
+    } else if unmapped.is_empty() {
+        0
+    } else {
+        1
+    })
+}
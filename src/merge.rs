@@ -0,0 +1,229 @@
+use crate::textdiff::{diff, Hunk};
+use crate::*;
+
+/// The result of a three-way merge.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Merge {
+    /// The merged text. Conflicting regions are enclosed with `<<<<<<<`, `=======` and `>>>>>>>` markers.
+    pub text: String,
+    /// The number of conflicting regions.
+    pub conflicts: usize,
+}
+
+/// Merges the changes made from `base` to `ours` and from `base` to `theirs`, like `diff3 -m`.
+///
+/// Changes touching the same lines of `base` conflict, unless both sides made the same change.
+/// The labels are written after the conflict markers.
+///
+/// ```rust
+/// use rcs_parser::merge3;
+///
+/// let merged = merge3("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n", ("ours", "theirs"));
+/// assert_eq!("A\nb\nC\n", merged.text);
+/// assert_eq!(0, merged.conflicts);
+///
+/// let merged = merge3("a\n", "b\n", "c\n", ("1.2", "1.2.2.1"));
+/// assert_eq!("<<<<<<< 1.2\nb\n=======\nc\n>>>>>>> 1.2.2.1\n", merged.text);
+/// ```
+pub fn merge3(base: &str, ours: &str, theirs: &str, labels: (&str, &str)) -> Merge {
+    let base = split_lines(base);
+    let ours = split_lines(ours);
+    let theirs = split_lines(theirs);
+    let ours_hunks = diff(&base, &ours);
+    let theirs_hunks = diff(&base, &theirs);
+
+    let mut changes: Vec<(usize, &Hunk)> = ours_hunks
+        .iter()
+        .map(|h| (0, h))
+        .chain(theirs_hunks.iter().map(|h| (1, h)))
+        .collect();
+    changes.sort_by_key(|(side, h)| (h.old.start, h.old.end, *side));
+
+    let sides = [&ours, &theirs];
+    let mut offsets = [0isize; 2];
+    let mut merged = Vec::new();
+    let mut conflicts = 0;
+    let mut pos = 0;
+    let mut i = 0;
+    while i < changes.len() {
+        // Collect the changes of both sides which overlap or touch each other.
+        let start = changes[i].1.old.start;
+        let mut end = changes[i].1.old.end;
+        let mut growth = [0isize; 2];
+        let mut changed = [false; 2];
+        while i < changes.len() && changes[i].1.old.start <= end {
+            let (side, hunk) = changes[i];
+            end = end.max(hunk.old.end);
+            growth[side] += hunk.new.len() as isize - hunk.old.len() as isize;
+            changed[side] = true;
+            i += 1;
+        }
+        merged.extend_from_slice(&base[pos..start]);
+        let region = |side: usize| {
+            let from = (start as isize + offsets[side]) as usize;
+            let to = (end as isize + offsets[side] + growth[side]) as usize;
+            &sides[side][from..to]
+        };
+        match changed {
+            [true, false] => merged.extend_from_slice(region(0)),
+            [false, true] => merged.extend_from_slice(region(1)),
+            _ if region(0) == region(1) => merged.extend_from_slice(region(0)),
+            _ => {
+                conflicts += 1;
+                merged.push(format!("<<<<<<< {}\n", labels.0));
+                push_terminated(&mut merged, region(0));
+                merged.push("=======\n".to_string());
+                push_terminated(&mut merged, region(1));
+                merged.push(format!(">>>>>>> {}\n", labels.1));
+            }
+        }
+        offsets[0] += growth[0];
+        offsets[1] += growth[1];
+        pos = end;
+    }
+    merged.extend_from_slice(&base[pos..]);
+    Merge {
+        text: merged.concat(),
+        conflicts,
+    }
+}
+
+/// Appends lines, adding a line terminator to the last one if it is missing, so a marker can follow.
+fn push_terminated(merged: &mut Vec<String>, lines: &[String]) {
+    merged.extend_from_slice(lines);
+    if let Some(last) = merged.last_mut() {
+        if !last.ends_with('\n') {
+            last.push('\n');
+        }
+    }
+}
+
+impl RcsData {
+    /// Returns the ancestors of a revision from the oldest trunk revision, ending with the revision itself.
+    ///
+    /// The trunk is followed up to the branching point, then each branch up to the next branching point.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(
+    ///     vec![num![1, 1], num![1, 2], num![1, 2, 2, 1], num![1, 2, 2, 2]],
+    ///     rcs.ancestry(&num![1, 2, 2, 2]).unwrap()
+    /// );
+    /// ```
+    pub fn ancestry(&self, rev: &Num) -> Result<Vec<Num>, Error> {
+        if !rev.is_valid_revision() {
+            return Err(Error::UnknownRevision(rev.clone()));
+        }
+        let mut targets = rev.get_branching_points();
+        targets.push(rev.clone());
+        let mut ancestry = Vec::new();
+        for (level, target) in targets.iter().enumerate() {
+            let branch = if level == 0 {
+                num![]
+            } else {
+                target.get_branch()
+            };
+            let revisions = self.branch_revisions(&branch)?;
+            let count = revisions
+                .iter()
+                .position(|num| num == target)
+                .ok_or_else(|| Error::UnknownRevision(rev.clone()))?;
+            ancestry.extend(revisions.into_iter().take(count + 1));
+        }
+        Ok(ancestry)
+    }
+
+    /// Finds the newest common ancestor of two revisions.
+    ///
+    /// If one revision is the ancestor of the other, it is returned.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(num![1, 2], rcs.common_ancestor(&num![1, 2, 1, 1], &num![1, 2, 2, 3]).unwrap());
+    /// assert_eq!(num![1, 2], rcs.common_ancestor(&num![2, 1], &num![1, 2, 2, 3]).unwrap());
+    /// ```
+    pub fn common_ancestor(&self, rev1: &Num, rev2: &Num) -> Result<Num, Error> {
+        let ancestry1 = self.ancestry(rev1)?;
+        let ancestry2 = self.ancestry(rev2)?;
+        ancestry1
+            .iter()
+            .zip(ancestry2.iter())
+            .take_while(|(a, b)| a == b)
+            .last()
+            .map(|(a, _)| a.clone())
+            .ok_or_else(|| Error::UnknownRevision(rev2.clone()))
+    }
+
+    /// Merges the changes of `theirs` into `ours`, using their common ancestor as base, like `rcsmerge`.
+    ///
+    /// Conflicts are marked with the revision numbers.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let merged = rcs.merge(&num![2, 1], &num![1, 2, 2, 3]).unwrap();
+    /// println!("{} conflicts\n{}", merged.conflicts, merged.text);
+    /// ```
+    pub fn merge(&self, ours: &Num, theirs: &Num) -> Result<Merge, Error> {
+        let base = self.common_ancestor(ours, theirs)?;
+        Ok(merge3(
+            &self.checkout(&base)?,
+            &self.checkout(ours)?,
+            &self.checkout(theirs)?,
+            (&ours.to_string(), &theirs.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn merge3() {
+        let base = "1\n2\n3\n4\n5\n";
+        let merged = super::merge3(base, "1\n2\n3\n4\n5\n6\n", "0\n1\n2\n3\n4\n5\n", ("a", "b"));
+        assert_eq!("0\n1\n2\n3\n4\n5\n6\n", merged.text);
+        assert_eq!(0, merged.conflicts);
+
+        let merged = super::merge3(base, "1\nx\n3\n4\n5\n", "1\n2\n3\ny\n5\n", ("a", "b"));
+        assert_eq!("1\nx\n3\ny\n5\n", merged.text);
+
+        let merged = super::merge3(base, "1\nx\n3\n4\n5\n", "1\nx\n3\n4\n5\n", ("a", "b"));
+        assert_eq!("1\nx\n3\n4\n5\n", merged.text);
+        assert_eq!(0, merged.conflicts);
+
+        let merged = super::merge3(base, "1\nx\n3\n4\n5", "1\n3\n4\n5\n", ("a", "b"));
+        assert_eq!("1\n<<<<<<< a\nx\n=======\n>>>>>>> b\n3\n4\n5", merged.text);
+        assert_eq!(1, merged.conflicts);
+    }
+
+    #[test]
+    fn merge() {
+        let rcs = parse_example("text1.txt,v");
+        assert_eq!(
+            Some(num![1, 1]),
+            rcs.ancestry(&num![2, 1]).unwrap().first().cloned()
+        );
+        assert!(matches!(
+            rcs.ancestry(&num![1, 2, 5, 1]),
+            Err(Error::UnknownRevision(_))
+        ));
+
+        let merged = rcs.merge(&num![1, 2], &num![1, 2, 2, 3]).unwrap();
+        assert_eq!(0, merged.conflicts);
+        assert_eq!(rcs.checkout(&num![1, 2, 2, 3]).unwrap(), merged.text);
+
+        let merged = rcs.merge(&num![1, 2, 1, 1], &num![1, 2, 1, 1]).unwrap();
+        assert_eq!(rcs.checkout(&num![1, 2, 1, 1]).unwrap(), merged.text);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::parsers::is_idchar;
+use crate::*;
+
+/// The log RCS stores when an empty log message is given.
+const EMPTY_LOG: &str = "*** empty log message ***";
+
+impl RcsData {
+    /// Resolves the revision of a metadata change. An empty revision means the newest revision of the default branch.
+    fn metadata_target(&self, rev: &str) -> Result<Num, Error> {
+        if rev.is_empty() {
+            self.default_head()
+        } else {
+            self.resolve_revision(rev)
+        }
+    }
+
+    /// Sets the state of a revision, like `rcs -sstate:rev`. Returns the changed revision.
+    ///
+    /// The revision is given by a number or by a symbolic name, a branch means its newest revision.
+    /// An empty revision means the newest revision of the default branch.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(num![1, 2, 2, 3], rcs.set_state("Fix2", "Rel").unwrap());
+    /// assert_eq!(Some("Rel".to_string()), rcs.deltas[&num![1, 2, 2, 3]].state);
+    /// assert!(rcs.set_state("1.1", "two words").is_err());
+    /// ```
+    pub fn set_state(&mut self, rev: &str, state: &str) -> Result<Num, Error> {
+        validate_id(state)?;
+        let num = self.metadata_target(rev)?;
+        self.deltas.get_mut(&num).unwrap().state = Some(state.to_string());
+        Ok(num)
+    }
+
+    /// Replaces the log of a revision, like `rcs -mrev:msg`. Returns the changed revision.
+    ///
+    /// Trailing white space of the message is removed and a newline is appended, as RCS does.
+    /// An empty message is stored as `*** empty log message ***`.
+    pub fn set_log(&mut self, rev: &str, log: &str) -> Result<Num, Error> {
+        let num = self.metadata_target(rev)?;
+        self.deltas.get_mut(&num).unwrap().log = clean_log(log);
+        Ok(num)
+    }
+
+    /// Sets the author of a revision. Returns the changed revision.
+    pub fn set_author(&mut self, rev: &str, author: &str) -> Result<Num, Error> {
+        validate_id(author)?;
+        let num = self.metadata_target(rev)?;
+        self.deltas.get_mut(&num).unwrap().author = author.to_string();
+        Ok(num)
+    }
+
+    /// Replaces an author login in all revisions and locks of the file. Returns the number of changed revisions.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(7, rcs.rename_author("dseres", "anonymous").unwrap());
+    /// assert_eq!(Some("anonymous"), rcs.locker(&num![2, 1]));
+    /// ```
+    pub fn rename_author(&mut self, author: &str, new_author: &str) -> Result<usize, Error> {
+        validate_id(new_author)?;
+        let mut count = 0;
+        for delta in self.deltas.values_mut().filter(|d| d.author == author) {
+            delta.author = new_author.to_string();
+            count += 1;
+        }
+        for (id, _) in self.locks.iter_mut().filter(|(id, _)| id == author) {
+            *id = new_author.to_string();
+        }
+        Ok(count)
+    }
+}
+
+/// Removes trailing white space of a log message and appends a newline, as RCS stores logs.
+pub(crate) fn clean_log(log: &str) -> String {
+    match log.trim_end() {
+        "" => format!("{}\n", EMPTY_LOG),
+        log => format!("{}\n", log),
+    }
+}
+
+/// Checks an identifier used as state or login: it consists of idchars and dots.
+pub(crate) fn validate_id(id: &str) -> Result<(), Error> {
+    if !id.is_empty() && id.chars().all(|c| is_idchar(c) || c == '.') {
+        Ok(())
+    } else {
+        Err(Error::InvalidId(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn set_state_and_author() {
+        let mut rcs = parse_example("text1.txt,v");
+        assert_eq!(num![2, 1], rcs.set_state("", "Exp").unwrap());
+        assert_eq!(Some("Exp".to_string()), rcs.deltas[&num![2, 1]].state);
+        assert!(matches!(rcs.set_state("1.1", ""), Err(Error::InvalidId(_))));
+        assert!(rcs.set_state("1.9", "Exp").is_err());
+        assert_eq!(num![1, 1], rcs.set_author("1.1", "j.doe").unwrap());
+        assert_eq!("j.doe", rcs.deltas[&num![1, 1]].author);
+        assert!(matches!(
+            rcs.set_author("1.1", "j;doe"),
+            Err(Error::InvalidId(_))
+        ));
+        assert_eq!(6, rcs.rename_author("dseres", "anonymous").unwrap());
+        assert_eq!(0, rcs.rename_author("dseres", "anonymous").unwrap());
+    }
+
+    #[test]
+    fn set_log() {
+        let mut rcs = parse_example("text1.txt,v");
+        let text = rcs.checkout(&num![1, 2]).unwrap();
+        rcs.set_log("v1_1", "redacted by @compliance \n\n").unwrap();
+        assert_eq!("redacted by @compliance\n", rcs.deltas[&num![1, 2]].log);
+        rcs.set_log("1.1", " ").unwrap();
+        assert_eq!("*** empty log message ***\n", rcs.deltas[&num![1, 1]].log);
+
+        let written = String::from_utf8(rcs.to_bytes()).unwrap();
+        let (_, written) = parse_rcs(written.as_str()).unwrap();
+        assert_eq!(rcs, written);
+        assert_eq!(text, written.checkout(&num![1, 2]).unwrap());
+    }
+}
@@ -7,7 +7,7 @@
 /// Num{numbers:vec![1,2,3,4]}
 /// # }
 /// ```
-#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Hash)]
 pub struct Num {
     ///The numbers of a revision number
     pub numbers: Vec<u32>,
@@ -24,15 +24,7 @@ pub struct Num {
 #[macro_export]
 macro_rules! num {
     ( ) => { Num{numbers: Vec::new()} };
-    ( $( $x:expr ),* ) => {
-        {
-            let mut temp_vec = Vec::new();
-            $(
-                temp_vec.push($x);
-            )*
-            Num{numbers: temp_vec}
-        }
-    };
+    ( $( $x:expr ),* ) => { Num{numbers: vec![$( $x ),*]} };
 }
 
 impl Num {
@@ -57,18 +49,19 @@ impl Num {
             && self.numbers.iter().all( |n| *n > 0)
     }
 
-    /// Shows if a num is a branch's number.
+    /// Shows if a num is a branch's number. CVS magic branch numbers are branch numbers too.
     /// 
     /// # Examples:
     /// ```
     /// use rcs_parser::{Num,num};
     /// assert_eq!(true, num![1].is_branch());
     /// assert_eq!(true, num![1,1,1].is_branch());
+    /// assert_eq!(true, num![1,2,0,4].is_branch());
     /// 
     /// assert_eq!(false, num![1,1].is_branch());
     /// ```
     pub fn is_branch(&self) -> bool {
-        self.numbers.len() % 2 == 1
+        self.numbers.len() % 2 == 1 || self.is_magic_branch()
     }
 
     /// Shows if a num is a revision's number.
@@ -81,19 +74,59 @@ impl Num {
     /// 
     /// assert_eq!(false, num![1].is_revision());
     /// assert_eq!(false, num![1,1,1].is_revision());
+    /// assert_eq!(false, num![1,2,0,4].is_revision());
     /// ```
     pub fn is_revision(&self) -> bool {
         !self.is_branch()
     }
 
+    /// Shows if a num is a CVS magic branch number.
+    ///
+    /// CVS stores branch tags with an extra zero before the last number, e.g. `1.2.0.4` means branch `1.2.4`.
+    ///
+    /// # Examples:
+    /// ```
+    /// use rcs_parser::{Num,num};
+    /// assert_eq!(true, num![1,2,0,4].is_magic_branch());
+    /// assert_eq!(true, num![1,2,4,1,0,2].is_magic_branch());
+    ///
+    /// assert_eq!(false, num![1,2,4].is_magic_branch());
+    /// assert_eq!(false, num![1,0].is_magic_branch());
+    /// ```
+    pub fn is_magic_branch(&self) -> bool {
+        let len = self.numbers.len();
+        len >= 4 && len % 2 == 0 && self.numbers[len - 2] == 0
+    }
+
+    /// Converts a CVS magic branch number to the real branch number. Other nums are returned as they are.
+    ///
+    /// # Examples:
+    /// ```
+    /// use rcs_parser::{Num,num};
+    /// assert_eq!(num![1,2,4], num![1,2,0,4].to_real_branch());
+    /// assert_eq!(num![1,2,4], num![1,2,4].to_real_branch());
+    /// ```
+    pub fn to_real_branch(&self) -> Num {
+        if self.is_magic_branch() {
+            let mut numbers = self.numbers.clone();
+            numbers.remove(numbers.len() - 2);
+            Num { numbers }
+        } else {
+            self.clone()
+        }
+    }
+
     /// Retreives the branching point of a num.
     /// ```
     /// use rcs_parser::{Num,num};
     /// assert_eq!(num![1,2], num![1,2,3].get_branching_point());
     /// assert_eq!(num![1,2], num![1,2,3,4].get_branching_point());
+    /// assert_eq!(num![1,2], num![1,2,0,4].get_branching_point());
     /// ```
     pub fn get_branching_point(&self) -> Num {
-        if self.is_branch() {
+        if self.is_magic_branch() {
+            self.to_real_branch().get_branching_point()
+        } else if self.is_branch() {
             let numbers = Vec::from(&(self.numbers[0..self.numbers.len() - 1]));
             Num { numbers }
         } else {
@@ -102,6 +135,25 @@ impl Num {
         }
     }
 
+    /// Retreives the branch of a revision. A branch number is returned as it is.
+    ///
+    /// # Examples:
+    /// ```
+    /// use rcs_parser::{Num,num};
+    /// assert_eq!(num![1,2,3], num![1,2,3,4].get_branch());
+    /// assert_eq!(num![1], num![1,2].get_branch());
+    /// assert_eq!(num![1,2,3], num![1,2,3].get_branch());
+    /// assert_eq!(num![1,2,4], num![1,2,0,4].get_branch());
+    /// ```
+    pub fn get_branch(&self) -> Num {
+        if self.is_branch() {
+            self.to_real_branch()
+        } else {
+            let numbers = Vec::from(&(self.numbers[0..self.numbers.len() - 1]));
+            Num { numbers }
+        }
+    }
+
     /// Retreives all branching points of a num.
     /// 
     /// # Examples:
@@ -120,3 +172,42 @@ impl Num {
         points
     }
 }
+
+impl std::fmt::Display for Num {
+    /// Formats the num as in RCS files.
+    ///
+    /// ```
+    /// use rcs_parser::{Num,num};
+    /// assert_eq!("1.2.3.4", num![1,2,3,4].to_string());
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, n) in self.numbers.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Num {
+    type Err = crate::Error;
+
+    /// Parses a revision number, e.g. `1.2.3.4`.
+    ///
+    /// ```
+    /// use rcs_parser::{Num,num};
+    /// assert_eq!(num![1,2,3,4], "1.2.3.4".parse().unwrap());
+    /// assert!("1.2x".parse::<Num>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::parsers::parse_num(s) {
+            Ok(("", num)) => Ok(num),
+            _ => Err(crate::Error::Parse(format!(
+                "invalid revision number '{}'",
+                s
+            ))),
+        }
+    }
+}
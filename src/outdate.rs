@@ -0,0 +1,191 @@
+use crate::textdiff::to_diff_commands;
+use crate::*;
+
+impl RcsData {
+    /// Returns the revision whose delta is the base of the delta of a revision: its predecessor on the trunk
+    /// (the revision with `next` pointing to it), or its predecessor on a branch, or its branching point.
+    /// The stored revision, the head in the layout of RCS, has no base.
+    pub(crate) fn delta_base(&self, rev: &Num) -> Option<Num> {
+        self.storage_parents().get(rev).map(|num| (*num).clone())
+    }
+
+    /// Returns the revisions of an `rcs -o` range, from the oldest to the newest.
+    ///
+    /// A single revision or a branch means that revision or the newest revision of the branch.
+    /// `rev1:rev2` means the revisions between the two on one branch, `rev1::rev2` excludes the ends.
+    /// An end can be omitted, meaning the beginning or the end of the branch.
+    fn outdate_range(&self, range: &str) -> Result<Vec<Num>, Error> {
+        let (from, to, exclusive) = match range.find(':') {
+            None => return Ok(vec![self.resolve_revision(range)?]),
+            Some(pos) => match range[pos + 1..].strip_prefix(':') {
+                Some(to) => (&range[..pos], to, true),
+                None => (&range[..pos], &range[pos + 1..], false),
+            },
+        };
+        let mut revisions = self.revision_range(&format!("{}:{}", from, to))?;
+        if exclusive {
+            for end in [from, to].iter().filter(|end| !end.is_empty()) {
+                let end = self.resolve_revision(end)?;
+                revisions.retain(|num| *num != end);
+            }
+        }
+        Ok(revisions)
+    }
+
+    /// Returns why a revision cannot be outdated: it is locked, it has branches or a symbolic name refers to it.
+    fn outdate_obstacle(&self, rev: &Num) -> Option<String> {
+        if let Some(locker) = self.locker(rev) {
+            return Some(format!("it is locked by {}", locker));
+        }
+        if !self.deltas[rev].branches.is_empty() {
+            return Some("it has branches".to_string());
+        }
+        self.symbols
+            .iter()
+            .find(|(_, num)| {
+                let num = num.to_real_branch();
+                num == *rev || (num.is_branch() && num.get_branching_point() == *rev)
+            })
+            .map(|(name, _)| format!("it has symbolic name {}", name))
+    }
+
+    /// Removes revisions, like `rcs -orange`. Returns the removed revisions from the oldest to the newest.
+    ///
+    /// The range is a revision or a branch (its newest revision), or `rev1:rev2` on one branch, where `rev1::rev2`
+    /// excludes the ends, and an omitted end means the beginning or the end of the branch. Revisions may be given by
+    /// symbolic names. The delta of the revision following the range
+    /// is recomputed, so every remaining revision keeps its content. If the head is removed, the newest remaining
+    /// trunk revision becomes the head. Revisions which are locked, have branches or symbolic names are not
+    /// removed, [Error::CannotOutdate] is returned instead. A re-headed file keeps its stored revision, unless it
+    /// is removed.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let text = rcs.checkout(&num![1, 2, 2, 3]).unwrap();
+    /// assert_eq!(vec![num![1, 2, 2, 1], num![1, 2, 2, 2]], rcs.outdate(":1.2.2.2").unwrap());
+    /// assert_eq!(text, rcs.checkout(&num![1, 2, 2, 3]).unwrap());
+    /// assert!(rcs.outdate("1.2").is_err());
+    /// ```
+    pub fn outdate(&mut self, range: &str) -> Result<Vec<Num>, Error> {
+        let stored = self.stored_revision().clone();
+        if stored != self.head {
+            // The deltas are recomputed in the layout of RCS, then the stored revision is restored if it remains.
+            let head = self.head.to_string();
+            self.rehead(&head)?;
+            let removed = self.outdate(range);
+            if self.deltas.contains_key(&stored) {
+                self.rehead(&stored.to_string())?;
+            }
+            return removed;
+        }
+        let removed = self.outdate_range(range)?;
+        let (oldest, newest) = match (removed.first(), removed.last()) {
+            (Some(oldest), Some(newest)) => (oldest.clone(), newest.clone()),
+            _ => return Ok(removed),
+        };
+        for rev in removed.iter() {
+            if let Some(reason) = self.outdate_obstacle(rev) {
+                return Err(Error::CannotOutdate(rev.clone(), reason));
+            }
+        }
+        // Trunk deltas are based on the newer revision, branch deltas on the older one.
+        let on_branch = oldest.numbers.len() > 2;
+        let (base, follower) = if on_branch {
+            (self.delta_base(&oldest), self.deltas[&newest].next.clone())
+        } else {
+            (self.delta_base(&newest), self.deltas[&oldest].next.clone())
+        };
+        if base.is_none() && follower.is_none() {
+            return Err(Error::CannotOutdate(
+                newest,
+                "no revision would remain on the trunk".to_string(),
+            ));
+        }
+        let text = match (&base, &follower) {
+            (Some(base), Some(follower)) => Some(Text::Diff(to_diff_commands(
+                &self.checkout_lines(base)?,
+                &self.checkout_lines(follower)?,
+            ))),
+            (None, Some(follower)) => Some(Text::Head(self.checkout_lines(follower)?.concat())),
+            _ => None,
+        };
+
+        match &base {
+            Some(base) if on_branch && self.deltas[base].branches.contains(&oldest) => {
+                let branches = &mut self.deltas.get_mut(base).unwrap().branches;
+                match &follower {
+                    Some(follower) => {
+                        let pos = branches.iter().position(|b| *b == oldest).unwrap();
+                        branches[pos] = follower.clone();
+                    }
+                    None => branches.retain(|b| *b != oldest),
+                }
+            }
+            Some(base) => self.deltas.get_mut(base).unwrap().next = follower.clone(),
+            None => self.head = follower.clone().unwrap(),
+        }
+        if let (Some(follower), Some(text)) = (follower, text) {
+            self.deltas.get_mut(&follower).unwrap().text = text;
+        }
+        for rev in removed.iter() {
+            self.deltas.remove(rev);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{contents, parse_example};
+    use crate::*;
+
+    #[test]
+    fn outdate_trunk() {
+        let mut rcs = parse_example("text1.txt,v");
+        let mut expected = contents(&rcs);
+        assert!(matches!(rcs.outdate("2.1"), Err(Error::CannotOutdate(..))));
+        rcs.break_lock("2.1").unwrap();
+        assert!(matches!(rcs.outdate("2.1"), Err(Error::CannotOutdate(..))));
+        rcs.delete_symbol("v2_1").unwrap();
+        assert_eq!(vec![num![2, 1]], rcs.outdate("2.1").unwrap());
+        assert_eq!(num![1, 2], rcs.head);
+        assert!(matches!(rcs.deltas[&num![1, 2]].text, Text::Head(_)));
+        expected.retain(|(num, _)| *num != num![2, 1]);
+        assert_eq!(expected, contents(&rcs));
+
+        assert_eq!(vec![num![1, 1]], rcs.outdate("1.1").unwrap());
+        assert_eq!(None, rcs.deltas[&num![1, 2]].next);
+        expected.retain(|(num, _)| *num != num![1, 1]);
+        assert_eq!(expected, contents(&rcs));
+        assert!(rcs.outdate("1.2").is_err());
+    }
+
+    #[test]
+    fn outdate_branch() {
+        let mut rcs = parse_example("text1.txt,v");
+        let mut expected = contents(&rcs);
+        assert_eq!(Vec::<Num>::new(), rcs.outdate("1.2.2.2::1.2.2.3").unwrap());
+        assert_eq!(
+            vec![num![1, 2, 2, 2]],
+            rcs.outdate("1.2.2.1::Fix2").unwrap()
+        );
+        assert_eq!(Some(num![1, 2, 2, 3]), rcs.deltas[&num![1, 2, 2, 1]].next);
+        expected.retain(|(num, _)| *num != num![1, 2, 2, 2]);
+        assert_eq!(expected, contents(&rcs));
+
+        assert_eq!(vec![num![1, 2, 2, 1]], rcs.outdate("1.2.2.1").unwrap());
+        assert_eq!(
+            vec![num![1, 2, 1, 1], num![1, 2, 2, 3]],
+            rcs.deltas[&num![1, 2]].branches
+        );
+        expected.retain(|(num, _)| *num != num![1, 2, 2, 1]);
+        assert_eq!(expected, contents(&rcs));
+        assert!(matches!(
+            rcs.outdate("1.2.2.3"),
+            Err(Error::CannotOutdate(..))
+        ));
+    }
+}
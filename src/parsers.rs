@@ -12,8 +12,8 @@ pub use admin::parse_admin;
 pub use chars::{is_idchar, is_special_chars, is_visible_char, parse_id, parse_sym};
 pub use combinators::{parse_value, parse_value_all_opt, parse_value_many0, parse_value_opt};
 pub use delta::parse_delta;
-pub use deltatext::{parse_deltatext, parse_deltatext_head, DeltaText};
+pub use deltatext::{parse_deltatext, parse_deltatext_head, parse_deltatext_log, DeltaText};
 pub use diff::{parse_diff_command, parse_diff_line};
 pub use num::parse_num;
-pub use rcsdata::parse_rcs;
+pub use rcsdata::{parse_desc, parse_rcs, parse_rcs_with_visitor};
 pub use string::{parse_intstring, parse_string};
@@ -7,7 +7,7 @@ use nom::{
     combinator::map,
     error::{context, VerboseError},
     multi::many0,
-    sequence::{preceded, terminated, tuple},
+    sequence::{pair, preceded, terminated, tuple},
     IResult,
 };
 
@@ -59,14 +59,14 @@ pub struct DeltaText {
 ///                 DiffCommand::Add(
 ///                     0,
 ///                     vec![
-///                         "The Way that can be told of is not the eternal Way;".to_string(),
-///                         "The name that can be named is not the eternal name.".to_string()
+///                         "The Way that can be told of is not the eternal Way;\n".to_string(),
+///                         "The name that can be named is not the eternal name.\n".to_string()
 ///                     ]
 ///                 ),
 ///                 DiffCommand::Delete(2, 2),
 ///                 DiffCommand::Add(
 ///                     3,
-///                     vec!["The Named is the mother of all things.".to_string()]
+///                     vec!["The Named is the mother of all things.\n".to_string()]
 ///                 ),
 ///                 DiffCommand::Delete(11, 3)
 ///             ]
@@ -102,6 +102,20 @@ pub fn parse_deltatext(input: &str) -> IResult<&str, DeltaText, VerboseError<&st
     Ok((input, DeltaText { num, log, text }))
 }
 
+/// Parses the revision number and the log of a deltatext.
+pub fn parse_deltatext_log(input: &str) -> IResult<&str, (Num, String), VerboseError<&str>> {
+    context(
+        "DeltaText",
+        pair(
+            parse_num,
+            preceded(
+                preceded(multispace1, tag("log")),
+                preceded(multispace1, parse_string),
+            ),
+        ),
+    )(input)
+}
+
 pub fn parse_deltatext_head(input: &str) -> IResult<&str, DeltaText, VerboseError<&str>> {
     let (input, (num, log, text)) = context(
         "DeltaText",
@@ -155,14 +169,14 @@ d11 3
                         DiffCommand::Add(
                             0,
                             vec![
-                                "The Way that can be told of is not the eternal Way;".to_string(),
-                                "The name that can be named is not the eternal name.".to_string()
+                                "The Way that can be told of is not the eternal Way;\n".to_string(),
+                                "The name that can be named is not the eternal name.\n".to_string()
                             ]
                         ),
                         DiffCommand::Delete(2, 2),
                         DiffCommand::Add(
                             3,
-                            vec!["The Named is the mother of all things.".to_string()]
+                            vec!["The Named is the mother of all things.\n".to_string()]
                         ),
                         DiffCommand::Delete(11, 3)
                     ])
@@ -7,7 +7,7 @@ use nom::{
     branch::alt,
     bytes::complete::{is_not, tag},
     character::complete::{digit1, line_ending, multispace0, multispace1, one_of, space0},
-    combinator::map,
+    combinator::{map, peek, verify},
     error::{context, VerboseError},
     multi::{count, many0},
     sequence::{pair, preceded, terminated, tuple},
@@ -37,7 +37,7 @@ use nom::{
 /// assert_eq!(
 ///     Ok((
 ///         "",
-///         DiffCommand::Add(23, vec!["First line added.".to_string(), "Second line added.".to_string()])
+///         DiffCommand::Add(23, vec!["First line added.\n".to_string(), "Second line added.\n".to_string()])
 ///     )),
 ///     parse_diff_command("a23 2\nFirst line added.\nSecond line added.\n")
 /// );
@@ -82,7 +82,10 @@ pub fn parse_diff_command(input: &str) -> IResult<&str, DiffCommand, VerboseErro
 ///
 /// Diff is part of a delimited '@string@', so it have to parse delimited '@@' too.
 ///
-/// This function cannot read Mac style line endings. ("\r").
+/// The line is returned with its terminator, so the original text can be reconstructed byte by byte.
+/// Lines are terminated by "\n" as in diff. A "\r" before it or anywhere in the line is kept, so
+/// "\r\n" and old Mac style "\r" line endings are preserved.
+/// The last line of a file may have no terminator at all, then it ends at the closing '@' of the string.
 ///
 /// Examples
 /// ```ignore
@@ -92,43 +95,44 @@ pub fn parse_diff_command(input: &str) -> IResult<&str, DiffCommand, VerboseErro
 ///     Err,
 /// };
 ///
-/// assert_eq!(Ok(("", "abc".to_string())), parse_diff_line("abc\n"));
-/// assert_eq!(Ok(("", "abc 123".to_string())), parse_diff_line("abc 123\r\n"));
-/// assert_eq!(Ok(("", "abc@abc".to_string())), parse_diff_line("abc@@abc\n"));
+/// assert_eq!(Ok(("", "abc\n".to_string())), parse_diff_line("abc\n"));
+/// assert_eq!(Ok(("", "abc 123\r\n".to_string())), parse_diff_line("abc 123\r\n"));
+/// assert_eq!(Ok(("", "abc@abc\n".to_string())), parse_diff_line("abc@@abc\n"));
+/// assert_eq!(Ok(("@", "no newline".to_string())), parse_diff_line("no newline@"));
 ///
 /// assert_eq!(
 ///     Err(Err::Error(VerboseError {
 ///         errors: vec![
-///             ("", VerboseErrorKind::Nom(ErrorKind::CrLf)),
+///             ("", VerboseErrorKind::Nom(ErrorKind::Tag)),
+///             ("abc", VerboseErrorKind::Nom(ErrorKind::Alt)),
 ///             ("abc", VerboseErrorKind::Context( "Diff"))
 ///         ]
 ///     })),
 ///     parse_diff_line("abc")
 /// );
-///
-/// assert_eq!(
-///     Err(Err::Error(VerboseError {
-///         errors: vec![
-///             ("@abc\n", VerboseErrorKind::Nom(ErrorKind::CrLf)),
-///             ("abc@abc\n", VerboseErrorKind::Context( "Diff"))
-///         ]
-///     })),
-///     parse_diff_line("abc@abc\n")
-/// );
 /// ```
 pub fn parse_diff_line(input: &str) -> IResult<&str, String, VerboseError<&str>> {
     context(
         CONTEXT,
-        terminated(
-            map(
-                many0(alt((is_not("@\r\n"), map(tag("@@"), |_| "@")))),
-                |v| v.concat(),
+        alt((
+            map(pair(parse_line_content, tag("\n")), |(mut line, _)| {
+                line.push('\n');
+                line
+            }),
+            verify(
+                terminated(parse_line_content, peek(tag("@"))),
+                |line: &String| !line.is_empty(),
             ),
-            line_ending,
-        ),
+        )),
     )(input)
 }
 
+fn parse_line_content(input: &str) -> IResult<&str, String, VerboseError<&str>> {
+    map(many0(alt((is_not("@\n"), map(tag("@@"), |_| "@")))), |v| {
+        v.concat()
+    })(input)
+}
+
 #[cfg(test)]
 mod test {
 
@@ -141,36 +145,36 @@ mod test {
     #[test]
     fn parse_diff_line() {
         assert_eq!(
-            Ok(("def", "abc".to_string())),
+            Ok(("def", "abc\n".to_string())),
             super::parse_diff_line("abc\ndef")
         );
         assert_eq!(
-            Ok(("def", "abc@abc".to_string())),
+            Ok(("def", "abc@abc\n".to_string())),
             super::parse_diff_line("abc@@abc\ndef")
         );
         assert_eq!(
-            Ok(("def", "abc@@abc".to_string())),
+            Ok(("def", "abc@@abc\n".to_string())),
             super::parse_diff_line("abc@@@@abc\ndef")
         );
         assert_eq!(
-            Ok(("def", "abc".to_string())),
+            Ok(("def", "abc\r\n".to_string())),
             super::parse_diff_line("abc\r\ndef")
         );
-        assert_eq!(Ok(("", "@".to_string())), super::parse_diff_line("@@\n"));
-        assert_eq!(Ok(("", "".to_string())), super::parse_diff_line("\n"));
         assert_eq!(
-            Err(Err::Error(VerboseError {
-                errors: vec![
-                    ("@abc\n", VerboseErrorKind::Nom(ErrorKind::CrLf)),
-                    ("abc@abc\n", VerboseErrorKind::Context(super::CONTEXT))
-                ]
-            })),
+            Ok(("@", "abc\rdef\r".to_string())),
+            super::parse_diff_line("abc\rdef\r@")
+        );
+        assert_eq!(Ok(("", "@\n".to_string())), super::parse_diff_line("@@\n"));
+        assert_eq!(Ok(("", "\n".to_string())), super::parse_diff_line("\n"));
+        assert_eq!(
+            Ok(("@abc\n", "abc".to_string())),
             super::parse_diff_line("abc@abc\n")
         );
         assert_eq!(
             Err(Err::Error(VerboseError {
                 errors: vec![
-                    ("", VerboseErrorKind::Nom(ErrorKind::CrLf)),
+                    ("", VerboseErrorKind::Nom(ErrorKind::Tag)),
+                    ("abc", VerboseErrorKind::Nom(ErrorKind::Alt)),
                     ("abc", VerboseErrorKind::Context(super::CONTEXT))
                 ]
             })),
@@ -179,11 +183,12 @@ mod test {
         assert_eq!(
             Err(Err::Error(VerboseError {
                 errors: vec![
-                    ("", VerboseErrorKind::Nom(ErrorKind::CrLf)),
-                    ("", VerboseErrorKind::Context(super::CONTEXT))
+                    ("@", VerboseErrorKind::Nom(ErrorKind::Verify)),
+                    ("@", VerboseErrorKind::Nom(ErrorKind::Alt)),
+                    ("@", VerboseErrorKind::Context(super::CONTEXT))
                 ]
             })),
-            super::parse_diff_line("")
+            super::parse_diff_line("@")
         );
     }
 
@@ -200,7 +205,7 @@ mod test {
         assert_eq!(
             Ok((
                 "",
-                DiffCommand::Add(1213, vec!["aaa".to_string(), "bbb".to_string()])
+                DiffCommand::Add(1213, vec!["aaa\n".to_string(), "bbb\n".to_string()])
             )),
             super::parse_diff_command("a1213 2\naaa\nbbb\n")
         );
@@ -216,7 +221,8 @@ mod test {
         assert_eq!(
             Err(Err::Error(VerboseError {
                 errors: vec![
-                    ("", VerboseErrorKind::Nom(ErrorKind::CrLf)),
+                    ("", VerboseErrorKind::Nom(ErrorKind::Tag)),
+                    ("", VerboseErrorKind::Nom(ErrorKind::Alt)),
                     ("", VerboseErrorKind::Context(super::CONTEXT)),
                     ("", VerboseErrorKind::Nom(ErrorKind::Count)),
                     ("", VerboseErrorKind::Context(super::CONTEXT)),
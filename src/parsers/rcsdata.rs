@@ -1,34 +1,125 @@
 #![allow(dead_code)]
 
-use crate::{parsers::*, *};
+use crate::{parsers::*, visitor::*, *};
 use nom::{
     bytes::complete::tag,
     character::complete::{line_ending, multispace0, multispace1},
-    error::{context, VerboseError},
-    multi::many0,
+    error::{context, VerboseError, VerboseErrorKind},
     sequence::preceded,
     IResult,
 };
-use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
 pub static CONTEXT: &str = "RCS";
 
 pub fn parse_rcs(input: &str) -> IResult<&str, RcsData, VerboseError<&str>> {
-    let (input, mut rcsdata) = context(CONTEXT, parse_admin)(input)?;
-    let (input, deltas) = context(CONTEXT, parse_deltas)(input)?;
+    let mut builder = RcsDataBuilder::default();
+    let (input, _) = parse_rcs_with_visitor(input, &mut builder)?;
+    let rcsdata = builder.build().expect("admin is always visited");
+    Ok((input, rcsdata))
+}
+
+/// Parses an RCS file, calling the visitor for each part instead of building an [RcsData].
+pub fn parse_rcs_with_visitor<'a, V: Visitor + ?Sized>(
+    input: &'a str,
+    visitor: &mut V,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    let (input, admin) = context(CONTEXT, parse_admin)(input)?;
+    for (name, num) in &admin.symbols {
+        visitor.on_symbol(name, num);
+    }
+    visitor.on_admin(admin);
+    let (input, nums) = context(CONTEXT, |i| visit_deltas(i, visitor))(input)?;
     let (input, desc) = context(CONTEXT, parse_desc)(input)?;
-    let (input, deltatexts) = context(CONTEXT, parse_deltatexts)(input)?;
+    visitor.on_desc(desc);
+    let (input, _) = context(CONTEXT, |i| visit_deltatexts(i, &nums, visitor))(input)?;
     let (input, _) = context(CONTEXT, line_ending)(input)?;
-    rcsdata.desc = desc;
-    rcsdata.deltas = build_deltas(deltas, deltatexts);
-    Ok((input, rcsdata))
+    Ok((input, ()))
 }
 
-fn parse_deltas(input: &str) -> IResult<&str, Vec<Delta>, VerboseError<&str>> {
-    context("deltas", many0(parse_delta))(input)
+/// Parses the deltas, calling the visitor for each one. Returns the revision numbers of the deltas.
+fn visit_deltas<'a, V: Visitor + ?Sized>(
+    mut input: &'a str,
+    visitor: &mut V,
+) -> IResult<&'a str, BTreeSet<Num>, VerboseError<&'a str>> {
+    let mut nums = BTreeSet::new();
+    loop {
+        match context("deltas", parse_delta)(input) {
+            Ok((rest, delta)) => {
+                nums.insert(delta.num.clone());
+                visitor.on_delta(delta);
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, nums)),
+            Err(e) => return Err(e),
+        }
+    }
 }
 
-fn parse_desc(input: &str) -> IResult<&str, String, VerboseError<&str>> {
+/// Fails if a deltatext belongs to a revision which has no delta.
+fn check_known<'a>(
+    input: &'a str,
+    num: &Num,
+    nums: &BTreeSet<Num>,
+) -> Result<(), nom::Err<VerboseError<&'a str>>> {
+    if nums.contains(num) {
+        Ok(())
+    } else {
+        Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context("unknown revision"))],
+        }))
+    }
+}
+
+/// Parses the deltatexts, calling the visitor for their logs and texts. Every deltatext must belong to
+/// one of `nums`. A malformed diff command fails with the revision of the deltatext in the context.
+fn visit_deltatexts<'a, V: Visitor + ?Sized>(
+    input: &'a str,
+    nums: &BTreeSet<Num>,
+    visitor: &mut V,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    let (start, _) = multispace0(input)?;
+    let (mut input, delta_head) = context("deltatexts", parse_deltatext_head)(start)?;
+    check_known(start, &delta_head.num, nums)?;
+    visit_deltatext(visitor, delta_head);
+    loop {
+        let (start, _) = multispace0(input)?;
+        let (rest, (num, log)) = match context("deltatexts", parse_deltatext_log)(start) {
+            Ok(result) => result,
+            Err(nom::Err::Error(_)) => return Ok((input, ())),
+            Err(e) => return Err(e),
+        };
+        check_known(start, &num, nums)?;
+        visitor.on_deltatext_log(&num, log);
+        let (mut rest, _) = context(
+            "deltatexts",
+            preceded(
+                preceded(multispace1, tag("text")),
+                preceded(multispace1, tag("@")),
+            ),
+        )(rest)?;
+        while !rest.starts_with('@') {
+            match parse_diff_command(rest) {
+                Ok((r, command)) => {
+                    visitor.on_diff_command(&num, command);
+                    rest = r;
+                }
+                Err(nom::Err::Error(mut e)) | Err(nom::Err::Failure(mut e)) => {
+                    e.errors
+                        .push((start, VerboseErrorKind::Context("deltatext")));
+                    e.errors
+                        .push((input, VerboseErrorKind::Context("deltatexts")));
+                    return Err(nom::Err::Failure(e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let (rest, _) = context("deltatexts", tag("@"))(rest)?;
+        input = rest;
+    }
+}
+
+pub fn parse_desc(input: &str) -> IResult<&str, String, VerboseError<&str>> {
     context(
         "desc",
         preceded(
@@ -38,32 +129,25 @@ fn parse_desc(input: &str) -> IResult<&str, String, VerboseError<&str>> {
     )(input)
 }
 
-fn parse_deltatexts(input: &str) -> IResult<&str, Vec<DeltaText>, VerboseError<&str>> {
-    let (input, delta_head) =
-        context("deltatexts", preceded(multispace0, parse_deltatext_head))(input)?;
-    let (input, mut deltatexts) =
-        context("deltatexts", many0(preceded(multispace0, parse_deltatext)))(input)?;
-    deltatexts.insert(0, delta_head);
-    Ok((input, deltatexts))
-}
-
-fn build_deltas(mut deltas: Vec<Delta>, mut texts: Vec<DeltaText>) -> BTreeMap<Num, Delta> {
-    let mut dtree = BTreeMap::new();
-    for d in deltas.drain(..) {
-        dtree.insert(d.num.clone(), d);
-    }
-    for t in texts.drain(..) {
-        let mut d = dtree.get_mut(&(t.num)).unwrap();
-        d.log = t.log;
-        d.text = t.text;
-    }
-    dtree
-}
-
 #[cfg(test)]
 mod test {
     use crate::*;
 
+    #[derive(Default)]
+    struct Collector {
+        deltas: Vec<Delta>,
+        logs: Vec<Num>,
+    }
+
+    impl Visitor for Collector {
+        fn on_delta(&mut self, delta: Delta) {
+            self.deltas.push(delta);
+        }
+        fn on_deltatext_log(&mut self, num: &Num, _: String) {
+            self.logs.push(num.clone());
+        }
+    }
+
     #[test]
     fn parse_deltas() {
         let input = r#"
@@ -80,9 +164,14 @@ branches
 	1.2.1.1
 	1.2.2.1;
 next	1.1;"#;
-        let (input, deltas) = super::parse_deltas(input).unwrap();
+        let mut collector = Collector::default();
+        let (input, nums) = super::visit_deltas(input, &mut collector).unwrap();
         assert_eq!("", input);
-        assert_eq!(2, deltas.len());
+        assert_eq!(2, collector.deltas.len());
+        assert_eq!(
+            vec![num![1, 2], num![2, 1]],
+            nums.into_iter().collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -142,9 +231,31 @@ They both may be called deep and profound.
 Deeper and more profound,
 The door of all subtleties!
 @"#;
-        let (input, deltatexts) = super::parse_deltatexts(input).unwrap();
+        let nums = vec![num![2, 1], num![1, 2]].into_iter().collect();
+        let mut collector = Collector::default();
+        let (input, _) = super::visit_deltatexts(input, &nums, &mut collector).unwrap();
         assert_eq!("", input);
-        assert_eq!(2, deltatexts.len());
+        assert_eq!(vec![num![2, 1], num![1, 2]], collector.logs);
+    }
+
+    #[test]
+    fn parse_rcs_errors() {
+        let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+        let malformed = contents.replacen("text\n@d1 2\n", "text\n@x1 2\n", 1);
+        assert_ne!(contents, malformed);
+        let err = Error::from_nom(super::parse_rcs(malformed.as_str()).unwrap_err());
+        assert_eq!(
+            "parse error: Diff < deltatext < deltatexts < RCS near 'x1 2'",
+            err.to_string()
+        );
+
+        let unknown = contents.replacen("\n\n\n1.1\nlog", "\n\n\n1.9\nlog", 1);
+        assert_ne!(contents, unknown);
+        let err = Error::from_nom(super::parse_rcs(unknown.as_str()).unwrap_err());
+        assert_eq!(
+            "parse error: unknown revision < RCS near '1.9'",
+            err.to_string()
+        );
     }
 
     #[test]
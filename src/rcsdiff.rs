@@ -0,0 +1,353 @@
+use crate::binary::bytes_to_text;
+use crate::textdiff::{diff, Hunk};
+use crate::*;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Differences of two texts, which can be printed as unified or context diff, like `rcsdiff -u` or `rcsdiff -c`.
+#[derive(Debug, Clone)]
+pub struct UnifiedDiff {
+    /// The label of the old text, written after `---` (unified) or `***` (context).
+    pub old_label: String,
+    /// The label of the new text, written after `+++` (unified) or `---` (context).
+    pub new_label: String,
+    old: Vec<String>,
+    new: Vec<String>,
+    hunks: Vec<Hunk>,
+    binary: bool,
+}
+
+/// Compares two revisions of a file, like `rcsdiff -rrev1 -rrev2 file`. Revisions may be on different branches.
+///
+/// Keywords are expanded as `co` would do, according to the mode of the file. Binary files (`-kb`) are only
+/// compared, their differences are formatted as `Binary files ... differ`. The labels are the revision numbers.
+///
+/// ```rust
+/// use rcs_parser::{diff_revisions, parse_rcs, num, Num};
+/// use std::path::Path;
+///
+/// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+/// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+/// let diff = diff_revisions(&rcs, &num![1, 2, 2, 3], &num![1, 2], Path::new("text1.txt,v")).unwrap();
+/// assert!(diff.unified(3).starts_with("--- 1.2.2.3\n+++ 1.2\n@@ "));
+/// ```
+pub fn diff_revisions(
+    rcs: &RcsData,
+    rev1: &Num,
+    rev2: &Num,
+    rcsfile: &Path,
+) -> Result<UnifiedDiff, Error> {
+    let old = bytes_to_text(&rcs.checkout_expanded(rev1, None, rcsfile, None)?);
+    let new = bytes_to_text(&rcs.checkout_expanded(rev2, None, rcsfile, None)?);
+    let mut diff = UnifiedDiff::new(
+        rev1.to_string(),
+        split_lines(&old),
+        rev2.to_string(),
+        split_lines(&new),
+    );
+    diff.binary = rcs.is_binary();
+    Ok(diff)
+}
+
+/// Compares a revision with a working file, like `rcsdiff -rrev file`.
+///
+/// Keywords of the revision are expanded as `co` would do, so unchanged keywords do not show up as differences.
+/// Binary files are handled as by [diff_revisions].
+pub fn diff_working(
+    rcs: &RcsData,
+    rev: &Num,
+    rcsfile: &Path,
+    working: &Path,
+) -> Result<UnifiedDiff, Error> {
+    let old = bytes_to_text(&rcs.checkout_expanded(rev, None, rcsfile, None)?);
+    let new = bytes_to_text(&std::fs::read(working)?);
+    let mut diff = UnifiedDiff::new(
+        format!("{} {}", rcsfile.display(), rev),
+        split_lines(&old),
+        working.display().to_string(),
+        split_lines(&new),
+    );
+    diff.binary = rcs.is_binary();
+    Ok(diff)
+}
+
+impl UnifiedDiff {
+    /// Compares two texts given by lines.
+    pub fn new(old_label: String, old: Vec<String>, new_label: String, new: Vec<String>) -> Self {
+        let hunks = diff(&old, &new);
+        UnifiedDiff {
+            old_label,
+            new_label,
+            old,
+            new,
+            hunks,
+            binary: false,
+        }
+    }
+
+    /// Shows if the texts are equal.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Formats the differences of binary texts as diff does, without showing them.
+    fn binary_differ(&self) -> String {
+        format!(
+            "Binary files {} and {} differ\n",
+            self.old_label, self.new_label
+        )
+    }
+
+    /// Groups the changes which are closer than twice the context, as diff prints them in one hunk.
+    fn groups(&self, context: usize) -> Vec<&[Hunk]> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        for i in 1..=self.hunks.len() {
+            if i == self.hunks.len()
+                || self.hunks[i].old.start - self.hunks[i - 1].old.end > 2 * context
+            {
+                groups.push(&self.hunks[start..i]);
+                start = i;
+            }
+        }
+        groups
+    }
+
+    /// Returns the old and new line ranges of a group, extended with the context.
+    fn group_ranges(&self, group: &[Hunk], context: usize) -> (usize, usize, usize, usize) {
+        let (first, last) = (&group[0], &group[group.len() - 1]);
+        let before = context.min(first.old.start);
+        let after = context.min(self.old.len() - last.old.end);
+        (
+            first.old.start - before,
+            last.old.end + after,
+            first.new.start - before,
+            last.new.end + after,
+        )
+    }
+
+    /// Formats the differences as unified diff with `context` lines of context.
+    ///
+    /// ```rust
+    /// use rcs_parser::{split_lines, UnifiedDiff};
+    ///
+    /// let diff = UnifiedDiff::new(
+    ///     "a 1.1".to_string(),
+    ///     split_lines("x\ny\nz\n"),
+    ///     "a 1.2".to_string(),
+    ///     split_lines("x\nY\nz\n"),
+    /// );
+    /// assert_eq!("--- a 1.1\n+++ a 1.2\n@@ -1,3 +1,3 @@\n x\n-y\n+Y\n z\n", diff.unified(3));
+    /// ```
+    pub fn unified(&self, context: usize) -> String {
+        let mut out = String::new();
+        if self.is_empty() {
+            return out;
+        }
+        if self.binary {
+            return self.binary_differ();
+        }
+        writeln!(out, "--- {}\n+++ {}", self.old_label, self.new_label).unwrap();
+        for group in self.groups(context) {
+            let (old_start, old_end, new_start, new_end) = self.group_ranges(group, context);
+            writeln!(
+                out,
+                "@@ -{} +{} @@",
+                unified_range(old_start, old_end),
+                unified_range(new_start, new_end)
+            )
+            .unwrap();
+            let mut pos = old_start;
+            for hunk in group {
+                push_lines(&mut out, " ", &self.old[pos..hunk.old.start]);
+                push_lines(&mut out, "-", &self.old[hunk.old.clone()]);
+                push_lines(&mut out, "+", &self.new[hunk.new.clone()]);
+                pos = hunk.old.end;
+            }
+            push_lines(&mut out, " ", &self.old[pos..old_end]);
+        }
+        out
+    }
+
+    /// Formats the differences as context diff with `context` lines of context.
+    ///
+    /// ```rust
+    /// use rcs_parser::{split_lines, UnifiedDiff};
+    ///
+    /// let diff = UnifiedDiff::new(
+    ///     "a 1.1".to_string(),
+    ///     split_lines("x\ny\n"),
+    ///     "a 1.2".to_string(),
+    ///     split_lines("x\ny\nz\n"),
+    /// );
+    /// assert_eq!(
+    ///     "*** a 1.1\n--- a 1.2\n***************\n*** 1,2 ****\n--- 1,3 ----\n  x\n  y\n+ z\n",
+    ///     diff.context(3)
+    /// );
+    /// ```
+    pub fn context(&self, context: usize) -> String {
+        let mut out = String::new();
+        if self.is_empty() {
+            return out;
+        }
+        if self.binary {
+            return self.binary_differ();
+        }
+        writeln!(out, "*** {}\n--- {}", self.old_label, self.new_label).unwrap();
+        for group in self.groups(context) {
+            let (old_start, old_end, new_start, new_end) = self.group_ranges(group, context);
+            writeln!(out, "***************").unwrap();
+            writeln!(out, "*** {} ****", context_range(old_start, old_end)).unwrap();
+            if group.iter().any(|h| !h.old.is_empty()) {
+                let mut pos = old_start;
+                for hunk in group {
+                    let mark = if hunk.new.is_empty() { "- " } else { "! " };
+                    push_lines(&mut out, "  ", &self.old[pos..hunk.old.start]);
+                    push_lines(&mut out, mark, &self.old[hunk.old.clone()]);
+                    pos = hunk.old.end;
+                }
+                push_lines(&mut out, "  ", &self.old[pos..old_end]);
+            }
+            writeln!(out, "--- {} ----", context_range(new_start, new_end)).unwrap();
+            if group.iter().any(|h| !h.new.is_empty()) {
+                let mut pos = new_start;
+                for hunk in group {
+                    let mark = if hunk.old.is_empty() { "+ " } else { "! " };
+                    push_lines(&mut out, "  ", &self.new[pos..hunk.new.start]);
+                    push_lines(&mut out, mark, &self.new[hunk.new.clone()]);
+                    pos = hunk.new.end;
+                }
+                push_lines(&mut out, "  ", &self.new[pos..new_end]);
+            }
+        }
+        out
+    }
+}
+
+/// Writes lines with a prefix. A missing line terminator is reported as diff does.
+fn push_lines(out: &mut String, prefix: &str, lines: &[String]) {
+    for line in lines {
+        out.push_str(prefix);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Formats a line range of a unified diff hunk header, `start,count`.
+fn unified_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        count => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Formats a line range of a context diff hunk header, `first,last`.
+fn context_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{}", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, end),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    fn diff(old: &str, new: &str) -> UnifiedDiff {
+        UnifiedDiff::new(
+            "old".to_string(),
+            split_lines(old),
+            "new".to_string(),
+            split_lines(new),
+        )
+    }
+
+    #[test]
+    fn unified() {
+        assert_eq!("", diff("a\n", "a\n").unified(3));
+        assert_eq!(
+            "--- old\n+++ new\n@@ -1,3 +1,2 @@\n a\n-b\n c\n@@ -8,2 +7,3 @@\n h\n-i\n+I\n+j\n",
+            diff("a\nb\nc\nd\ne\nf\ng\nh\ni\n", "a\nc\nd\ne\nf\ng\nh\nI\nj\n").unified(1)
+        );
+        assert_eq!(
+            "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n",
+            diff("", "a\n").unified(3)
+        );
+        assert_eq!(
+            "--- old\n+++ new\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+a\n",
+            diff("a", "a\n").unified(3)
+        );
+    }
+
+    #[test]
+    fn context() {
+        assert_eq!(
+            "*** old\n--- new\n***************\n*** 1,3 ****\n  a\n! b\n! c\n--- 1,2 ----\n  a\n! B\n",
+            diff("a\nb\nc\n", "a\nB\n").context(1)
+        );
+        assert_eq!(
+            "*** old\n--- new\n***************\n*** 1 ****\n- a\n--- 0 ----\n",
+            diff("a\n", "").context(3)
+        );
+    }
+
+    #[test]
+    fn diff_revisions() {
+        let rcs = parse_example("text1.txt,v");
+        let rcsfile = std::path::Path::new("examples/text1.txt,v");
+        assert!(
+            super::diff_revisions(&rcs, &num![1, 1], &num![2, 1], rcsfile)
+                .unwrap()
+                .is_empty()
+        );
+        let diff = super::diff_revisions(&rcs, &num![1, 2, 2, 3], &num![1, 2], rcsfile).unwrap();
+        assert!(!diff.is_empty());
+        assert!(diff.context(3).starts_with("*** 1.2.2.3\n--- 1.2\n"));
+
+        let dir = std::env::temp_dir().join(format!("rcs-parser-rcsdiff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let working = dir.join("text1.txt");
+        std::fs::write(&working, rcs.checkout(&num![2, 1]).unwrap()).unwrap();
+        assert!(super::diff_working(&rcs, &num![2, 1], rcsfile, &working)
+            .unwrap()
+            .is_empty());
+        std::fs::write(&working, "changed\n").unwrap();
+        let diff = super::diff_working(&rcs, &num![2, 1], rcsfile, &working).unwrap();
+        assert!(diff
+            .unified(0)
+            .starts_with("--- examples/text1.txt,v 2.1\n+++ "));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diff_keywords_and_binary() {
+        let (_, mut rcs) = parse_rcs(
+            "head\t1.2;\naccess;\nsymbols;\nlocks; strict;\ncomment\t@# @;\n\n\
+             1.2\ndate\t2021.01.02.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t1.1;\n\n\
+             1.1\ndate\t2021.01.01.00.00.00;\tauthor a;\tstate Exp;\nbranches;\nnext\t;\n\n\
+             desc\n@@\n\n\
+             1.2\nlog\n@@\ntext\n@$Revision$\nx\n@\n\n\
+             1.1\nlog\n@@\ntext\n@d2 1\na2 1\ny\n@\n",
+        )
+        .unwrap();
+        let rcsfile = std::path::Path::new("a,v");
+        assert_eq!(
+            "--- 1.1\n+++ 1.2\n@@ -1,2 +1,2 @@\n-$Revision: 1.1 $\n-y\n+$Revision: 1.2 $\n+x\n",
+            super::diff_revisions(&rcs, &num![1, 1], &num![1, 2], rcsfile)
+                .unwrap()
+                .unified(3)
+        );
+        rcs.expand = Some("b".to_string());
+        let diff = super::diff_revisions(&rcs, &num![1, 2], &num![1, 2], rcsfile).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!("", diff.unified(3));
+        let diff = super::diff_revisions(&rcs, &num![1, 1], &num![1, 2], rcsfile).unwrap();
+        assert_eq!("Binary files 1.1 and 1.2 differ\n", diff.unified(3));
+        assert_eq!("Binary files 1.1 and 1.2 differ\n", diff.context(3));
+    }
+}
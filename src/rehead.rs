@@ -0,0 +1,148 @@
+use crate::checkout::apply_diff;
+use crate::textdiff::to_diff_commands;
+use crate::*;
+
+impl RcsData {
+    /// Changes the storage of the file so that a revision is stored in full. Returns the revision.
+    ///
+    /// The deltas between the [stored revision](RcsData::stored_revision) and the revision are recomputed in the
+    /// other direction, so every revision keeps its content. The `next` and `branches` links and the head are not
+    /// changed, so the history, e.g. [trunk_history](RcsData::trunk_history) or [rlog_order](RcsData::rlog_order),
+    /// stays the same. Re-heading to the head restores the layout of RCS.
+    ///
+    /// RCS expects the head stored in full, so it cannot check out revisions of a re-headed file, such files are
+    /// meant for fast checkouts with this crate. The file is written with the stored revision as the first delta
+    /// text, as [parse_rcs] expects it.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num, Text};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let text = rcs.checkout(&num![1, 1]).unwrap();
+    /// assert_eq!(num![1, 1], rcs.rehead("1.1").unwrap());
+    /// assert!(matches!(rcs.deltas[&num![1, 1]].text, Text::Head(_)));
+    /// assert_eq!(num![2, 1], rcs.head);
+    /// assert_eq!(text, rcs.checkout(&num![1, 1]).unwrap());
+    /// ```
+    pub fn rehead(&mut self, rev: &str) -> Result<Num, Error> {
+        let num = self.resolve_revision(rev)?;
+        let path = self.revision_path(&num)?;
+        let mut texts = Vec::with_capacity(path.len());
+        let mut lines = Vec::new();
+        for n in path.iter() {
+            lines = match &self.deltas[n].text {
+                Text::Head(text) => split_lines(text),
+                Text::Diff(commands) => {
+                    apply_diff(lines, commands).ok_or_else(|| Error::InvalidDiff(n.clone()))?
+                }
+            };
+            texts.push(lines.clone());
+        }
+
+        for (i, pair) in path.windows(2).enumerate() {
+            self.deltas.get_mut(&pair[0]).unwrap().text =
+                Text::Diff(to_diff_commands(&texts[i + 1], &texts[i]));
+        }
+        if let Some(text) = texts.pop() {
+            self.deltas.get_mut(&num).unwrap().text = Text::Head(text.concat());
+        }
+        Ok(num)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{contents, parse_example};
+    use crate::*;
+
+    fn links(rcs: &RcsData) -> Vec<(Num, Option<Num>, Vec<Num>)> {
+        rcs.deltas
+            .values()
+            .map(|d| (d.num.clone(), d.next.clone(), d.branches.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn rehead() {
+        let mut rcs = parse_example("text1.txt,v");
+        let (expected, original) = (contents(&rcs), links(&rcs));
+        assert_eq!(num![1, 2, 2, 2], rcs.rehead("1.2.2.2").unwrap());
+        assert_eq!(num![1, 2, 2, 2], *rcs.stored_revision());
+        assert_eq!(num![2, 1], rcs.head);
+        assert_eq!(original, links(&rcs));
+        assert_eq!(
+            vec![num![1, 2, 2, 2], num![1, 2, 2, 1], num![1, 2], num![1, 1]],
+            rcs.revision_path(&num![1, 1]).unwrap()
+        );
+        assert_eq!(expected, contents(&rcs));
+        for revision in rcs.revisions() {
+            let (num, _, content) = revision.unwrap();
+            assert_eq!(rcs.checkout(&num).unwrap(), content);
+        }
+
+        let written = rcs.to_bytes();
+        assert!(written.starts_with(b"head\t2.1;\n"));
+        assert_eq!(rcs, parse_rcs_bytes(&written).unwrap());
+
+        assert_eq!(num![2, 1], rcs.rehead("2.1").unwrap());
+        assert_eq!(num![2, 1], *rcs.stored_revision());
+        assert_eq!(original, links(&rcs));
+        assert_eq!(expected, contents(&rcs));
+        assert!(rcs.rehead("1.9").is_err());
+    }
+
+    #[test]
+    fn history_after_rehead() {
+        let original = parse_example("text1.txt,v");
+        let mut rcs = original.clone();
+        rcs.rehead("1.2.2.2").unwrap();
+        assert_eq!(
+            original.default_head().unwrap(),
+            rcs.default_head().unwrap()
+        );
+        assert_eq!(
+            original.trunk_history(true).unwrap(),
+            rcs.trunk_history(true).unwrap()
+        );
+        assert_eq!(original.rlog_order(), rcs.rlog_order());
+        let date = parse_date("2021-04-10 09:41").unwrap();
+        assert_eq!(
+            Some(num![1, 2, 1, 1]),
+            rcs.revision_at(Some(&num![1, 2, 1]), &date).unwrap()
+        );
+        assert_eq!(Some(num![2, 1]), rcs.revision_at(None, &date).unwrap());
+        for num in original.deltas.keys() {
+            assert_eq!(
+                original.line_stats(num).unwrap(),
+                rcs.line_stats(num).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn change_after_rehead() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.rehead("1.1").unwrap();
+        let checkin = CheckIn::new("dseres", b"new\n");
+        assert_eq!(num![2, 2], rcs.checkin(&checkin).unwrap());
+        assert_eq!(num![2, 2], rcs.head);
+        assert_eq!(num![1, 1], *rcs.stored_revision());
+        assert_eq!("new\n", rcs.checkout(&num![2, 2]).unwrap());
+
+        let expected = contents(&rcs);
+        assert_eq!(vec![num![1, 2, 2, 1]], rcs.outdate("1.2.2.1").unwrap());
+        assert_eq!(num![1, 1], *rcs.stored_revision());
+        let mut remaining = expected.clone();
+        remaining.retain(|(num, _)| *num != num![1, 2, 2, 1]);
+        assert_eq!(remaining, contents(&rcs));
+    }
+
+    #[test]
+    fn rehead_head() {
+        let mut rcs = parse_example("text1.txt,v");
+        let original = rcs.clone();
+        assert_eq!(num![2, 1], rcs.rehead("2.1").unwrap());
+        assert_eq!(original, rcs);
+    }
+}
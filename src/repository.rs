@@ -0,0 +1,179 @@
+use crate::*;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Finds comma-v files in a directory and in its subdirectories. Paths are returned ordered.
+pub fn find_rcs_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.to_string_lossy().ends_with(",v") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads and parses a comma-v file. Errors tell the path of the file.
+///
+/// Files are parsed with [parse_rcs_bytes], so files which are not valid UTF-8 can be read too.
+pub fn read_rcs_file(path: &Path) -> Result<RcsData, Error> {
+    let in_file = |e: Error| Error::InFile(path.to_path_buf(), Box::new(e));
+    let bytes = std::fs::read(path).map_err(|e| in_file(e.into()))?;
+    parse_rcs_bytes(&bytes).map_err(in_file)
+}
+
+/// Returns the path of the lock file RCS uses while a comma-v file is rewritten: `dir/foo,v` is locked by `dir/,foo,`.
+///
+/// ```rust
+/// use rcs_parser::lock_file_path;
+/// use std::path::Path;
+/// assert_eq!(Path::new("src/RCS/,main.c,"), lock_file_path(Path::new("src/RCS/main.c,v")));
+/// ```
+pub fn lock_file_path(rcsfile: &Path) -> PathBuf {
+    let name = rcsfile
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = name.strip_suffix(",v").unwrap_or(&name);
+    rcsfile.with_file_name(format!(",{},", name))
+}
+
+/// Writes a comma-v file safely, following the protocol of GNU RCS.
+///
+/// The lock file given by [lock_file_path] is created exclusively, so concurrent writers, including `ci` and `co -l`,
+/// are refused with [Error::LockFileExists]. The content is written to the lock file and synced to the disk,
+/// then the lock file is renamed to the comma-v file in one step, and the directory is synced so the rename
+/// survives a crash. The permissions of an existing file are kept,
+/// new files are read-only as RCS creates them. The lock file is removed if anything fails.
+pub fn write_rcs_file(path: &Path, rcs: &RcsData) -> Result<(), Error> {
+    let in_file = |e: Error| Error::InFile(path.to_path_buf(), Box::new(e));
+    let lock = lock_file_path(path);
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&lock) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(in_file(Error::LockFileExists(lock)))
+        }
+        Err(e) => return Err(in_file(e.into())),
+    };
+    let result = (|| -> io::Result<()> {
+        file.write_all(&rcs.to_bytes())?;
+        let permissions = match fs::metadata(path) {
+            Ok(metadata) => metadata.permissions(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut permissions = file.metadata()?.permissions();
+                permissions.set_readonly(true);
+                permissions
+            }
+            Err(e) => return Err(e),
+        };
+        file.set_permissions(permissions)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&lock, path)?;
+        sync_dir(path)
+    })();
+    result.map_err(|e| {
+        let _ = fs::remove_file(&lock);
+        in_file(e.into())
+    })
+}
+
+/// Syncs the directory of a file to the disk, so a rename in it is not lost.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened to be synced on other systems.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Returns the path of the working file of a comma-v file.
+///
+/// The `,v` suffix is removed, and `Attic` (removed files of CVS) and `RCS` directories are left out.
+///
+/// ```rust
+/// use rcs_parser::working_path;
+/// use std::path::Path;
+/// assert_eq!(Path::new("src/main.c"), working_path(Path::new("src/Attic/main.c,v")));
+/// assert_eq!(Path::new("doc/a.txt"), working_path(Path::new("doc/RCS/a.txt,v")));
+/// ```
+pub fn working_path(rcsfile: &Path) -> PathBuf {
+    let mut path = PathBuf::new();
+    if let Some(parent) = rcsfile.parent() {
+        for component in parent.components() {
+            if component.as_os_str() != "Attic" && component.as_os_str() != "RCS" {
+                path.push(component);
+            }
+        }
+    }
+    if let Some(name) = rcsfile.file_name() {
+        let name = name.to_string_lossy();
+        path.push(name.strip_suffix(",v").unwrap_or(&name));
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+    use std::path::Path;
+
+    #[test]
+    fn find_rcs_files() {
+        let files = super::find_rcs_files(Path::new("examples")).unwrap();
+        assert!(files.contains(&Path::new("examples/text1.txt,v").to_path_buf()));
+        assert!(files.iter().all(|f| f.to_string_lossy().ends_with(",v")));
+    }
+
+    #[test]
+    fn read_rcs_file() {
+        let rcs = super::read_rcs_file(Path::new("examples/text1.txt,v")).unwrap();
+        assert_eq!(parse_example("text1.txt,v"), rcs);
+        match super::read_rcs_file(Path::new("examples/missing,v")) {
+            Err(Error::InFile(path, _)) => assert_eq!(Path::new("examples/missing,v"), path),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn write_rcs_file() {
+        let dir = std::env::temp_dir().join(format!("rcs-parser-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("text1.txt,v");
+        let _ = std::fs::remove_file(&path);
+        let mut rcs = super::read_rcs_file(Path::new("examples/text1.txt,v")).unwrap();
+
+        super::write_rcs_file(&path, &rcs).unwrap();
+        assert_eq!(rcs, super::read_rcs_file(&path).unwrap());
+        assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+        assert!(!dir.join(",text1.txt,").exists());
+
+        std::fs::write(dir.join(",text1.txt,"), "").unwrap();
+        rcs.set_state("1.1", "Exp").unwrap();
+        assert!(matches!(
+            super::write_rcs_file(&path, &rcs),
+            Err(Error::InFile(_, e)) if matches!(*e, Error::LockFileExists(_))
+        ));
+        std::fs::remove_file(dir.join(",text1.txt,")).unwrap();
+        super::write_rcs_file(&path, &rcs).unwrap();
+        assert_eq!(rcs, super::read_rcs_file(&path).unwrap());
+        assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
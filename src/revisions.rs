@@ -0,0 +1,148 @@
+use crate::checkout::apply_diff;
+use crate::*;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Iterator over the revisions of a file with their contents, see [revisions](RcsData::revisions).
+#[derive(Debug)]
+pub struct Revisions<'a> {
+    rcs: &'a RcsData,
+    children: HashMap<&'a Num, Vec<&'a Num>>,
+    lines: Vec<String>,
+    /// Revisions to visit, the last one first, with the text of their parent if it is not the current text.
+    pending: Vec<(&'a Num, Option<Rc<Vec<String>>>)>,
+    /// Number of revisions visited, more than the number of deltas means a loop in the links.
+    visited: usize,
+}
+
+impl<'a> Iterator for Revisions<'a> {
+    type Item = Result<(Num, &'a Delta, String), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (num, parent) = self.pending.pop()?;
+        self.visited += 1;
+        if self.visited > self.rcs.deltas.len() {
+            self.pending.clear();
+            return Some(Err(Error::UnknownRevision(num.clone())));
+        }
+        let delta = match self.rcs.get_delta(num) {
+            Ok(delta) => delta,
+            Err(e) => {
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        };
+        if let Some(parent) = parent {
+            self.lines = parent.as_ref().clone();
+        }
+        let lines = std::mem::take(&mut self.lines);
+        self.lines = match &delta.text {
+            Text::Head(text) => split_lines(text),
+            Text::Diff(commands) => match apply_diff(lines, commands) {
+                Some(lines) => lines,
+                None => {
+                    self.pending.clear();
+                    return Some(Err(Error::InvalidDiff(num.clone())));
+                }
+            },
+        };
+
+        let children = self.children.get(num).map_or(&[][..], Vec::as_slice);
+        if children.len() > 1 {
+            let text = Rc::new(self.lines.clone());
+            for child in children[1..].iter().rev() {
+                self.pending.push((child, Some(text.clone())));
+            }
+        }
+        if let Some(first) = children.first() {
+            self.pending.push((first, None));
+        }
+        Some(Ok((num.clone(), delta, self.lines.concat())))
+    }
+}
+
+impl RcsData {
+    /// Returns an iterator over all revisions with their contents, applying each delta once.
+    ///
+    /// Revisions are visited along the storage links: the [stored revision](RcsData::stored_revision) first,
+    /// which is head unless the file was re-headed, then for each revision its branches, each forwards,
+    /// then the previous revision of the trunk. So every revision follows the one its delta is
+    /// based on. Beside the current text, the text of a revision is kept only while its branches are visited.
+    /// The iteration stops after the first error, a loop in the links is reported as [Error::UnknownRevision].
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let order: Vec<Num> = rcs.revisions().map(|r| r.unwrap().0).collect();
+    /// assert_eq!(
+    ///     vec![
+    ///         num![2, 1],
+    ///         num![1, 2],
+    ///         num![1, 2, 1, 1],
+    ///         num![1, 2, 2, 1],
+    ///         num![1, 2, 2, 2],
+    ///         num![1, 2, 2, 3],
+    ///         num![1, 1]
+    ///     ],
+    ///     order
+    /// );
+    /// ```
+    pub fn revisions(&self) -> Revisions<'_> {
+        Revisions {
+            rcs: self,
+            children: self.storage_children(),
+            lines: Vec::new(),
+            pending: vec![(self.stored_revision(), None)],
+            visited: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn revisions() {
+        for file in ["text1.txt,v", "vendor.c,v", "removed.c,v", "vendoronly.c,v"] {
+            let rcs = parse_example(file);
+            let mut count = 0;
+            for revision in rcs.revisions() {
+                let (num, delta, content) = revision.unwrap();
+                assert_eq!(num, delta.num);
+                assert_eq!(rcs.checkout(&num).unwrap(), content, "{} {}", file, num);
+                count += 1;
+            }
+            assert_eq!(rcs.deltas.len(), count);
+        }
+    }
+
+    #[test]
+    fn revisions_error() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.deltas.get_mut(&num![1, 2]).unwrap().text =
+            Text::Diff(vec![DiffCommand::Delete(99, 1)]);
+        let mut revisions = rcs.revisions();
+        assert!(revisions.next().unwrap().is_ok());
+        assert!(matches!(
+            revisions.next(),
+            Some(Err(Error::InvalidDiff(num))) if num == num![1, 2]
+        ));
+        assert!(revisions.next().is_none());
+    }
+
+    #[test]
+    fn revisions_loop() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.deltas.get_mut(&num![1, 1]).unwrap().next = Some(num![1, 2]);
+        let results: Vec<_> = rcs.revisions().collect();
+        assert_eq!(rcs.deltas.len() + 1, results.len());
+        assert!(matches!(
+            results.last(),
+            Some(Err(Error::UnknownRevision(_)))
+        ));
+    }
+}
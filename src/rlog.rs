@@ -0,0 +1,407 @@
+use crate::date::date_fields;
+use crate::*;
+use std::collections::BTreeSet;
+
+/// Selects revisions like the options of `rlog`.
+///
+/// Revisions selected by `-r` and `-b` are united, then they are intersected with the revisions
+/// selected by `-d`, `-s`, `-w` and `-l`. Options which are not given do not filter.
+///
+/// ```rust
+/// use rcs_parser::{parse_rcs, num, Num, RevisionFilter};
+///
+/// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+/// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+/// let filter = RevisionFilter::from_args(&["-r:v1_1,Fix2", "-sExp,beta"]).unwrap();
+/// let selected: Vec<&Num> = rcs.select(&filter).unwrap().iter().map(|d| &d.num).collect();
+/// assert_eq!(vec![&num![1, 2], &num![1, 2, 2, 3]], selected);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RevisionFilter {
+    revisions: Option<Vec<String>>,
+    dates: Option<Vec<String>>,
+    states: Option<Vec<String>>,
+    authors: Option<Vec<String>>,
+    lockers: Option<Vec<String>>,
+    default_branch: bool,
+}
+
+/// Splits a list of an option, e.g. `Production,beta`.
+fn split_list(list: &str, separator: char) -> Vec<String> {
+    list.split(separator)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Appends the elements of a list to an option.
+fn extend(option: Option<Vec<String>>, list: Vec<String>) -> Option<Vec<String>> {
+    let mut all = option.unwrap_or_default();
+    all.extend(list);
+    Some(all)
+}
+
+impl RevisionFilter {
+    /// Creates a filter which selects all revisions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses rlog options, e.g. `-r1.2:1.5`, `-d2020-01-01<2021-01-01`, `-sExp`, `-wdseres`, `-l`, `-b`.
+    pub fn from_args(args: &[&str]) -> Result<Self, Error> {
+        let mut filter = Self::new();
+        for arg in args {
+            let value = arg.get(2..).unwrap_or("");
+            filter = match arg.get(..2) {
+                Some("-r") => filter.revisions(value),
+                Some("-d") => filter.dates(value),
+                Some("-s") => filter.states(value),
+                Some("-w") => filter.authors(value),
+                Some("-l") => filter.locked_by(value),
+                Some("-b") if value.is_empty() => filter.default_branch(),
+                _ => return Err(Error::Parse(format!("unknown rlog option {}", arg))),
+            };
+        }
+        Ok(filter)
+    }
+
+    /// `-rrevisions`: comma separated revisions and ranges: `rev`, `rev1:rev2`, `:rev`, `rev:`, `branch`
+    /// and `branch.`. Symbolic names can be used. An empty list selects the newest revision of the default branch.
+    pub fn revisions(self, revisions: &str) -> Self {
+        RevisionFilter {
+            revisions: extend(self.revisions, split_list(revisions, ',')),
+            ..self
+        }
+    }
+
+    /// `-ddates`: semicolon separated dates and ranges: `d1<d2`, `d2>d1`, `<d`, `d>`, `d<`, `>d`.
+    /// Ranges are exclusive, unless `<` or `>` is followed by `=`. A single date `d` selects the newest
+    /// revision dated `d` or earlier.
+    pub fn dates(self, dates: &str) -> Self {
+        RevisionFilter {
+            dates: extend(self.dates, split_list(dates, ';')),
+            ..self
+        }
+    }
+
+    /// `-sstates`: comma separated states.
+    pub fn states(self, states: &str) -> Self {
+        RevisionFilter {
+            states: extend(self.states, split_list(states, ',')),
+            ..self
+        }
+    }
+
+    /// `-wlogins`: comma separated authors. An empty list means the current user.
+    pub fn authors(self, logins: &str) -> Self {
+        let mut logins = split_list(logins, ',');
+        if logins.is_empty() {
+            logins.extend(std::env::var("LOGNAME").or_else(|_| std::env::var("USER")));
+        }
+        RevisionFilter {
+            authors: extend(self.authors, logins),
+            ..self
+        }
+    }
+
+    /// `-llockers`: revisions locked by the comma separated users. An empty list means any user.
+    pub fn locked_by(self, lockers: &str) -> Self {
+        RevisionFilter {
+            lockers: extend(self.lockers, split_list(lockers, ',')),
+            ..self
+        }
+    }
+
+    /// `-b`: revisions of the default branch.
+    pub fn default_branch(self) -> Self {
+        RevisionFilter {
+            default_branch: true,
+            ..self
+        }
+    }
+}
+
+impl RcsData {
+    /// Returns all revisions in the order as `rlog` prints them.
+    ///
+    /// The trunk comes first from head downwards. Then the branches follow, starting with the branches of
+    /// the oldest revisions; each branch from its newest revision.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(
+    ///     vec![num![2, 1], num![1, 2], num![1, 1], num![1, 2, 2, 3], num![1, 2, 2, 2], num![1, 2, 2, 1], num![1, 2, 1, 1]],
+    ///     rcs.rlog_order()
+    /// );
+    /// ```
+    pub fn rlog_order(&self) -> Vec<Num> {
+        let mut order = self.chain(&self.head);
+        self.put_tree(&self.head, &mut order);
+        order
+    }
+
+    /// Follows the `next` fields from a revision.
+    fn chain(&self, start: &Num) -> Vec<Num> {
+        let mut chain = Vec::new();
+        let mut current = Some(start.clone());
+        while let Some(num) = current {
+            current = self.deltas.get(&num).and_then(|d| d.next.clone());
+            if self.deltas.contains_key(&num) {
+                chain.push(num);
+            }
+        }
+        chain
+    }
+
+    /// Appends the branches of a chain of revisions, as `putree` of rlog.
+    fn put_tree(&self, root: &Num, order: &mut Vec<Num>) {
+        for num in self.chain(root).iter().rev() {
+            for branch in self.deltas[num].branches.iter().rev() {
+                order.extend(self.chain(branch).into_iter().rev());
+                self.put_tree(branch, order);
+            }
+        }
+    }
+
+    /// Returns the deltas selected by a filter, in rlog order.
+    pub fn select(&self, filter: &RevisionFilter) -> Result<Vec<&Delta>, Error> {
+        let mut selected: Option<BTreeSet<Num>> = None;
+        if let Some(revisions) = &filter.revisions {
+            let mut set = BTreeSet::new();
+            if revisions.is_empty() {
+                set.insert(self.default_head()?);
+            }
+            for range in revisions {
+                set.extend(self.revision_range(range)?);
+            }
+            selected = Some(set);
+        }
+        if filter.default_branch {
+            let branch = match &self.branch {
+                Some(branch) => branch.clone(),
+                None => Num {
+                    numbers: self.head.numbers.iter().take(1).cloned().collect(),
+                },
+            };
+            selected
+                .get_or_insert_with(BTreeSet::new)
+                .extend(self.branch_revisions(&branch)?);
+        }
+        if let Some(dates) = &filter.dates {
+            let mut set = BTreeSet::new();
+            for range in dates {
+                set.extend(self.date_range(range)?);
+            }
+            selected = Some(match selected {
+                Some(s) => s.intersection(&set).cloned().collect(),
+                None => set,
+            });
+        }
+        let mut result = Vec::new();
+        for num in self.rlog_order() {
+            let delta = &self.deltas[&num];
+            let locked = |lockers: &Vec<String>| {
+                self.locks
+                    .iter()
+                    .any(|(id, n)| *n == num && (lockers.is_empty() || lockers.contains(id)))
+            };
+            if selected.as_ref().map_or(true, |s| s.contains(&num))
+                && filter.states.as_ref().map_or(true, |states| {
+                    states.contains(&delta.state.clone().unwrap_or_default())
+                })
+                && filter
+                    .authors
+                    .as_ref()
+                    .map_or(true, |a| a.contains(&delta.author))
+                && filter.lockers.as_ref().map_or(true, locked)
+            {
+                result.push(delta);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Resolves a revision or a branch of a `-r` range. Magic branch numbers are converted to real branches.
+    fn range_end(&self, name: &str) -> Result<Num, Error> {
+        let num = match name.parse::<Num>() {
+            Ok(num) => num,
+            Err(_) => self.get_symbol(name)?.clone(),
+        };
+        Ok(num.to_real_branch())
+    }
+
+    /// Returns the revisions of the branch of a revision. Trunk revisions belong to the whole trunk.
+    fn revisions_of_branch(&self, num: &Num) -> Result<Vec<Num>, Error> {
+        match num.numbers.len() {
+            0..=2 => self.branch_revisions(&num![]),
+            _ => self.branch_revisions(&num.get_branch()),
+        }
+    }
+
+    /// Returns the revisions of a `-r` range.
+    pub(crate) fn revision_range(&self, range: &str) -> Result<Vec<Num>, Error> {
+        if let Some(branch) = range.strip_suffix('.') {
+            return Ok(vec![self.branch_tip(&self.range_end(branch)?)?]);
+        }
+        let (from, to) = match range.find(':') {
+            Some(pos) => (&range[..pos], &range[pos + 1..]),
+            None => {
+                let num = self.range_end(range)?;
+                return if num.is_branch() {
+                    self.branch_revisions(&num)
+                } else {
+                    Ok(vec![self.get_delta(&num)?.num.clone()])
+                };
+            }
+        };
+        let from = if from.is_empty() {
+            None
+        } else {
+            Some(self.range_end(from)?)
+        };
+        let to = if to.is_empty() {
+            None
+        } else {
+            Some(self.range_end(to)?)
+        };
+        let reference = from
+            .as_ref()
+            .or(to.as_ref())
+            .ok_or_else(|| Error::Parse(format!("invalid revision range '{}'", range)))?;
+        let revisions = self.revisions_of_branch(reference)?;
+        let position = |end: &Option<Num>, first: bool| -> Result<usize, Error> {
+            match end {
+                None if first => Ok(0),
+                None => Ok(revisions.len()),
+                Some(num) if num.is_branch() => Ok(if first { 0 } else { revisions.len() }),
+                Some(num) => revisions
+                    .iter()
+                    .position(|n| n == num)
+                    .map(|p| if first { p } else { p + 1 })
+                    .ok_or_else(|| Error::UnknownRevision(num.clone())),
+            }
+        };
+        let (start, end) = (position(&from, true)?, position(&to, false)?);
+        let (start, end) = if start < end {
+            (start, end)
+        } else {
+            (end.saturating_sub(1), start + 1)
+        };
+        Ok(revisions[start..end.min(revisions.len())].to_vec())
+    }
+
+    /// Returns the revisions of a `-d` range.
+    fn date_range(&self, range: &str) -> Result<Vec<Num>, Error> {
+        let parse = |date: &str| -> Result<Option<[u32; 6]>, Error> {
+            let date = date.trim();
+            if date.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(date_fields(&parse_date(date)?)))
+            }
+        };
+        let split = range
+            .find(['<', '>'])
+            .map(|pos| (pos, range[pos + 1..].starts_with('=')));
+        let (after, before, inclusive) = match split {
+            None => {
+                let date = parse(range)?;
+                let newest = self
+                    .deltas
+                    .values()
+                    .filter(|d| Some(date_fields(&d.date)) <= date)
+                    .max_by_key(|d| date_fields(&d.date));
+                return Ok(newest.map(|d| d.num.clone()).into_iter().collect());
+            }
+            Some((pos, inclusive)) => {
+                let left = parse(&range[..pos])?;
+                let right = parse(&range[pos + if inclusive { 2 } else { 1 }..])?;
+                if &range[pos..pos + 1] == "<" {
+                    (left, right, inclusive)
+                } else {
+                    (right, left, inclusive)
+                }
+            }
+        };
+        Ok(self
+            .deltas
+            .values()
+            .filter(|d| {
+                let date = date_fields(&d.date);
+                let after_ok = after.map_or(true, |a| date > a || (inclusive && date == a));
+                let before_ok = before.map_or(true, |b| date < b || (inclusive && date == b));
+                after_ok && before_ok
+            })
+            .map(|d| d.num.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    fn select(args: &[&str]) -> Vec<Num> {
+        let rcs = parse_example("text1.txt,v");
+        let filter = RevisionFilter::from_args(args).unwrap();
+        rcs.select(&filter)
+            .unwrap()
+            .iter()
+            .map(|d| d.num.clone())
+            .collect()
+    }
+
+    #[test]
+    fn revisions() {
+        assert_eq!(7, select(&[]).len());
+        assert_eq!(vec![num![2, 1], num![1, 2]], select(&["-r1.2:2.1"]));
+        assert_eq!(vec![num![2, 1], num![1, 2]], select(&["-r2.1:1.2"]));
+        assert_eq!(vec![num![1, 2], num![1, 1]], select(&["-r:v1_1"]));
+        assert_eq!(vec![num![2, 1], num![1, 2]], select(&["-r1.2:"]));
+        assert_eq!(vec![num![1, 2, 2, 3]], select(&["-rFix2"]));
+        assert_eq!(
+            vec![num![1, 2, 2, 3], num![1, 2, 2, 2], num![1, 2, 2, 1]],
+            select(&["-r1.2.2"])
+        );
+        assert_eq!(
+            vec![num![1, 2, 2, 3], num![1, 2, 2, 2]],
+            select(&["-r1.2.2.2:"])
+        );
+        assert_eq!(vec![num![1, 2, 2, 3]], select(&["-r1.2.2."]));
+        assert_eq!(vec![num![2, 1]], select(&["-r"]));
+        assert_eq!(vec![num![2, 1]], select(&["-b"]));
+        assert_eq!(
+            vec![num![2, 1], num![1, 2, 1, 1]],
+            select(&["-b", "-rFix1"])
+        );
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(vec![num![2, 1], num![1, 2]], select(&["-sProduction,beta"]));
+        assert_eq!(7, select(&["-wdseres"]).len());
+        assert!(select(&["-wnobody"]).is_empty());
+        assert_eq!(vec![num![2, 1]], select(&["-l"]));
+        assert!(select(&["-lnobody"]).is_empty());
+        assert!(RevisionFilter::from_args(&["-x"]).is_err());
+    }
+
+    #[test]
+    fn dates() {
+        let all = select(&["-d2000-01-01<2100-01-01"]);
+        assert_eq!(7, all.len());
+        assert!(select(&["-d<2000-01-01"]).is_empty());
+        assert_eq!(7, select(&["-d>2000-01-01"]).len());
+        assert_eq!(vec![num![1, 2]], select(&["-d2021/03/25 10:16:43"]));
+        assert_eq!(
+            vec![num![1, 2]],
+            select(&["-d2021/03/25 10:16:43<=2021/03/25 10:16:43"])
+        );
+        assert!(select(&["-d2021/03/25 10:16:43<2021/03/25 10:16:43"]).is_empty());
+    }
+}
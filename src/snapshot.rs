@@ -0,0 +1,238 @@
+use crate::{date::date_fields, *};
+use std::path::{Path, PathBuf};
+
+/// Selects the revisions of a snapshot.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Selection {
+    /// The newest revision at a date, like `co -d`. The date is given by [parse_date].
+    /// If a branch name is given, the revisions are selected on that branch, otherwise on the default branch.
+    Date(Num, Option<String>),
+    /// The revision of a symbolic name, like `co -r`. A branch name selects the newest revision of the branch.
+    Symbol(String),
+}
+
+/// Result of a snapshot.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    /// Working files written, with the revision which was checked out.
+    pub files: Vec<(PathBuf, Num)>,
+    /// Comma-v files having no alive revision in the snapshot.
+    pub skipped: Vec<PathBuf>,
+    /// Comma-v files which cannot be read or checked out, each error is an [Error::InFile].
+    pub failed: Vec<Error>,
+}
+
+impl RcsData {
+    /// Returns the newest revision at a date on a branch, or on the default branch if `branch` is `None`.
+    ///
+    /// If nothing was committed on the branch before the date, the branching point is returned.
+    /// `None` is returned if the file did not exist at the date.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, parse_date, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// let date = parse_date("2021-04-01").unwrap();
+    /// assert_eq!(Some(num![1, 2]), rcs.revision_at(None, &date).unwrap());
+    /// ```
+    pub fn revision_at(&self, branch: Option<&Num>, date: &Num) -> Result<Option<Num>, Error> {
+        let date = date_fields(date);
+        let (revisions, point) = match branch {
+            Some(branch) if branch.numbers.len() > 1 => {
+                let branch = branch.to_real_branch();
+                (
+                    self.branch_revisions(&branch)?,
+                    Some(branch.get_branching_point()),
+                )
+            }
+            _ => (self.trunk_history(true)?, None),
+        };
+        let mut selected = None;
+        for num in revisions.into_iter().rev().chain(point) {
+            if date_fields(&self.get_delta(&num)?.date) <= date {
+                selected = Some(num);
+                break;
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Selects the revision of a snapshot. `None` is returned if the file is not part of the snapshot:
+    /// the symbolic name or branch does not exist, or the selected revision is dead.
+    pub fn select_revision(&self, selection: &Selection) -> Result<Option<Num>, Error> {
+        let selected = match selection {
+            Selection::Symbol(name) => match self.resolve_symbol(name) {
+                Err(Error::UnknownSymbol(_)) => None,
+                r => Some(r?),
+            },
+            Selection::Date(date, None) => self.revision_at(None, date)?,
+            Selection::Date(date, Some(name)) => match self.get_symbol(name) {
+                Ok(branch) if branch.is_branch() => self.revision_at(Some(branch), date)?,
+                _ => None,
+            },
+        };
+        Ok(selected.filter(|num| !self.is_dead(num)))
+    }
+}
+
+/// Materializes the working tree of a repository as it was at a date or at a symbolic name.
+///
+/// Each comma-v file under `repository` is checked out into `output`, keeping the directory structure
+/// (`Attic` and `RCS` directories are left out). Keywords are substituted according to `expand`, or to
+/// the mode of each file if it is `None`, `$Name$` gets the symbolic name of the selection. Dead revisions and files without the selected revision are skipped.
+///
+/// A comma-v file which cannot be read or checked out does not stop the snapshot, its error is collected in
+/// [failed](Snapshot::failed). Errors of listing the repository or writing the working files are returned.
+pub fn snapshot(
+    repository: &Path,
+    output: &Path,
+    selection: &Selection,
+    expand: Option<Expand>,
+) -> Result<Snapshot, Error> {
+    let mut result = Snapshot::default();
+    let name = match selection {
+        Selection::Symbol(name) => Some(name.as_str()),
+        Selection::Date(..) => None,
+    };
+    for rcsfile in repository::find_rcs_files(repository)? {
+        let in_file = |e: Error| Error::InFile(rcsfile.clone(), Box::new(e));
+        let rcs = match repository::read_rcs_file(&rcsfile) {
+            Ok(rcs) => rcs,
+            Err(e) => {
+                result.failed.push(e);
+                continue;
+            }
+        };
+        let selected = rcs.select_revision(selection).and_then(|num| match num {
+            Some(num) => Ok(Some((
+                rcs.checkout_expanded(&num, name, &rcsfile, expand)?,
+                num,
+            ))),
+            None => Ok(None),
+        });
+        let (content, num) = match selected {
+            Ok(Some(selected)) => selected,
+            Ok(None) => {
+                result.skipped.push(rcsfile);
+                continue;
+            }
+            Err(e) => {
+                result.failed.push(in_file(e));
+                continue;
+            }
+        };
+        let relative = rcsfile.strip_prefix(repository).unwrap_or(&rcsfile);
+        let target = output.join(repository::working_path(relative));
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, content)?;
+        result.files.push((target, num));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+    use std::path::Path;
+
+    #[test]
+    fn revision_at() {
+        let rcs = parse_example("text1.txt,v");
+        let date = parse_date("2021-03-25 10:15").unwrap();
+        assert_eq!(Some(num![1, 1]), rcs.revision_at(None, &date).unwrap());
+        let date = parse_date("2021-01-01").unwrap();
+        assert_eq!(None, rcs.revision_at(None, &date).unwrap());
+        let date = parse_date("2030-01-01").unwrap();
+        assert_eq!(
+            Some(num![1, 2, 2, 3]),
+            rcs.revision_at(Some(&num![1, 2, 2]), &date).unwrap()
+        );
+        let rcs = parse_example("vendor.c,v");
+        let date = parse_date("2021-02-15").unwrap();
+        assert_eq!(
+            Some(num![1, 1, 1, 2]),
+            rcs.revision_at(None, &date).unwrap()
+        );
+    }
+
+    #[test]
+    fn select_revision() {
+        let rcs = parse_example("removed.c,v");
+        let date = parse_date("2021-07-01").unwrap();
+        assert_eq!(
+            None,
+            rcs.select_revision(&Selection::Date(date.clone(), None))
+                .unwrap()
+        );
+        assert_eq!(
+            Some(num![1, 1, 2, 3]),
+            rcs.select_revision(&Selection::Date(date, Some("B".to_string())))
+                .unwrap()
+        );
+        let date = parse_date("2021-02-15").unwrap();
+        assert_eq!(
+            None,
+            rcs.select_revision(&Selection::Date(date, Some("B".to_string())))
+                .unwrap()
+        );
+        assert_eq!(
+            Some(num![1, 2]),
+            rcs.select_revision(&Selection::Symbol("REL_1".to_string()))
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            rcs.select_revision(&Selection::Symbol("REL_2".to_string()))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn snapshot() {
+        let output =
+            std::env::temp_dir().join(format!("rcs-parser-snapshot-{}", std::process::id()));
+        let result = super::snapshot(
+            Path::new("examples"),
+            &output,
+            &Selection::Symbol("RELEASE_1".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(2, result.files.len());
+        assert_eq!(
+            "line1\nline2\n",
+            std::fs::read_to_string(output.join("vendor.c")).unwrap()
+        );
+        assert!(result
+            .skipped
+            .contains(&Path::new("examples/text1.txt,v").to_path_buf()));
+        assert!(result.failed.is_empty());
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn snapshot_failed() {
+        let dir = std::env::temp_dir().join(format!("rcs-parser-failed-{}", std::process::id()));
+        let (repository, output) = (dir.join("repo"), dir.join("out"));
+        std::fs::create_dir_all(&repository).unwrap();
+        std::fs::copy("examples/vendor.c,v", repository.join("vendor.c,v")).unwrap();
+        std::fs::write(repository.join("broken.c,v"), "head\t1.1;\ngarbage").unwrap();
+        let result = super::snapshot(
+            &repository,
+            &output,
+            &Selection::Symbol("RELEASE_1".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(1, result.files.len());
+        assert_eq!(1, result.failed.len());
+        assert!(
+            matches!(&result.failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v"))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
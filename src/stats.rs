@@ -0,0 +1,216 @@
+use crate::{date::date_fields, *};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::AddAssign;
+use std::path::Path;
+
+/// Numbers of added and removed lines, as `rlog` prints them: `lines: +12 -3`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LineStats {
+    /// Number of added lines.
+    pub added: u64,
+    /// Number of removed lines.
+    pub removed: u64,
+}
+
+impl AddAssign for LineStats {
+    fn add_assign(&mut self, other: LineStats) {
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+}
+
+/// Counts the lines added and removed by diff commands.
+fn count(commands: &[DiffCommand]) -> LineStats {
+    let mut stats = LineStats::default();
+    for command in commands {
+        match command {
+            DiffCommand::Add(_, lines) => stats.added += lines.len() as u64,
+            DiffCommand::Delete(_, count) => stats.removed += *count as u64,
+            DiffCommand::Head(_) => {}
+        }
+    }
+    stats
+}
+
+impl RcsData {
+    /// Returns the lines changed by a revision compared to its predecessor.
+    ///
+    /// Trunk deltas are reverse diffs, so the change of a trunk revision is computed from the delta of
+    /// the previous revision, with added and removed lines swapped. Branch deltas are forward diffs.
+    /// In a re-headed file the deltas between head and the stored revision go the other way, they are counted
+    /// accordingly. `None` is returned for the initial revision, which has no predecessor.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, LineStats, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(None, rcs.line_stats(&num![1, 1]).unwrap());
+    /// assert_eq!(Some(LineStats { added: 5, removed: 3 }), rcs.line_stats(&num![1, 2]).unwrap());
+    /// ```
+    pub fn line_stats(&self, rev: &Num) -> Result<Option<LineStats>, Error> {
+        self.line_stats_in(rev, &self.link_parents(), &self.storage_parents())
+    }
+
+    /// Returns the lines changed by a revision, given the parents by the links and in the storage tree.
+    fn line_stats_in(
+        &self,
+        rev: &Num,
+        link_parents: &HashMap<&Num, &Num>,
+        storage_parents: &HashMap<&Num, &Num>,
+    ) -> Result<Option<LineStats>, Error> {
+        let delta = self.get_delta(rev)?;
+        let previous = if rev.numbers.len() > 2 {
+            link_parents.get(rev).copied()
+        } else {
+            delta.next.as_ref()
+        };
+        let previous = match previous {
+            Some(previous) => self.get_delta(previous)?,
+            None => return Ok(None),
+        };
+        if storage_parents.get(rev) == Some(&&previous.num) {
+            return Ok(match &delta.text {
+                Text::Diff(commands) => Some(count(commands)),
+                Text::Head(_) => None,
+            });
+        }
+        Ok(match &previous.text {
+            Text::Diff(commands) => {
+                let reverse = count(commands);
+                Some(LineStats {
+                    added: reverse.removed,
+                    removed: reverse.added,
+                })
+            }
+            Text::Head(_) => None,
+        })
+    }
+
+    /// Returns the name of a branch from the symbols, if it has one.
+    fn branch_name(&self, branch: &Num) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|(_, num)| num.is_branch() && num.to_real_branch() == *branch)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Changed lines of a repository summed by author, by month and by branch.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Churn {
+    /// Changes by the login of the authors.
+    pub by_author: BTreeMap<String, LineStats>,
+    /// Changes by year and month of the revisions.
+    pub by_month: BTreeMap<(u32, u32), LineStats>,
+    /// Changes by branch. Branches are named by their symbols, or by their numbers if they have no name.
+    /// The trunk is called `trunk`.
+    pub by_branch: BTreeMap<String, LineStats>,
+}
+
+impl Churn {
+    /// Adds the changes of all revisions of a file. Nothing is added if a revision fails.
+    pub fn add(&mut self, rcs: &RcsData) -> Result<(), Error> {
+        let (link_parents, storage_parents) = (rcs.link_parents(), rcs.storage_parents());
+        let mut changes = Vec::new();
+        for (num, delta) in rcs.deltas.iter() {
+            if let Some(stats) = rcs.line_stats_in(num, &link_parents, &storage_parents)? {
+                changes.push((num, delta, stats));
+            }
+        }
+        for (num, delta, stats) in changes {
+            let [year, month, ..] = date_fields(&delta.date);
+            let branch = if num.numbers.len() > 2 {
+                let branch = num.get_branch();
+                rcs.branch_name(&branch)
+                    .map(String::from)
+                    .unwrap_or_else(|| branch.to_string())
+            } else {
+                "trunk".to_string()
+            };
+            *self.by_author.entry(delta.author.clone()).or_default() += stats;
+            *self.by_month.entry((year, month)).or_default() += stats;
+            *self.by_branch.entry(branch).or_default() += stats;
+        }
+        Ok(())
+    }
+}
+
+/// Sums the changed lines of all comma-v files in a directory and its subdirectories.
+///
+/// A comma-v file which cannot be read does not stop the summing: the churn is returned together with the errors
+/// of such files, each error is an [Error::InFile]. Errors of listing the repository are returned.
+pub fn repository_churn(repository: &Path) -> Result<(Churn, Vec<Error>), Error> {
+    let mut churn = Churn::default();
+    let mut failed = Vec::new();
+    for path in find_rcs_files(repository)? {
+        let result = read_rcs_file(&path).and_then(|rcs| {
+            churn
+                .add(&rcs)
+                .map_err(|e| Error::InFile(path.clone(), Box::new(e)))
+        });
+        if let Err(e) = result {
+            failed.push(e);
+        }
+    }
+    Ok((churn, failed))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn line_stats() {
+        let rcs = parse_example("text1.txt,v");
+        for num in rcs.deltas.keys() {
+            let stats = match rcs.line_stats(num).unwrap() {
+                Some(stats) => stats,
+                None => continue,
+            };
+            let previous = rcs.ancestry(num).unwrap().into_iter().rev().nth(1).unwrap();
+            let old = rcs.checkout_lines(&previous).unwrap().len() as i64;
+            let new = rcs.checkout_lines(num).unwrap().len() as i64;
+            assert_eq!(new - old, stats.added as i64 - stats.removed as i64);
+        }
+        assert!(matches!(
+            rcs.line_stats(&num![3, 1]),
+            Err(Error::UnknownRevision(_))
+        ));
+    }
+
+    #[test]
+    fn churn() {
+        let rcs = parse_example("removed.c,v");
+        let mut churn = Churn::default();
+        churn.add(&rcs).unwrap();
+        let total = |stats: Vec<&LineStats>| {
+            let mut total = LineStats::default();
+            for s in stats {
+                total += *s;
+            }
+            total
+        };
+        let by_author = total(churn.by_author.values().collect());
+        let by_month = total(churn.by_month.values().collect());
+        let by_branch = total(churn.by_branch.values().collect());
+        assert_eq!(by_author, by_month);
+        assert_eq!(by_author, by_branch);
+        assert!(churn.by_branch.contains_key("trunk"));
+        assert!(churn.by_branch.contains_key("B"));
+    }
+
+    #[test]
+    fn repository_churn() {
+        let dir = std::env::temp_dir().join(format!("rcs-parser-churn-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy("examples/removed.c,v", dir.join("removed.c,v")).unwrap();
+        std::fs::write(dir.join("broken.c,v"), "head\t1.1;\ngarbage").unwrap();
+        let (churn, failed) = super::repository_churn(&dir).unwrap();
+        assert!(churn.by_branch.contains_key("B"));
+        assert_eq!(1, failed.len());
+        assert!(matches!(&failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
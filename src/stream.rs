@@ -0,0 +1,452 @@
+use crate::{binary::bytes_to_text, parsers::*, visitor::visit_deltatext, *};
+use std::io::{BufRead, BufReader, Read};
+
+/// Minimal amount of bytes read from the underlying reader at once.
+/// Larger amounts are read for long items, see [fill](RcsReader::fill).
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// One part of a comma-v file, as it is yielded by [RcsReader].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    ///The admin part. Only the admin fields are filled, `desc` is empty and `deltas` contains nothing.
+    Admin(RcsData),
+    ///A delta, without log and text.
+    Delta(Delta),
+    ///The description of the file.
+    Desc(String),
+    ///Log and text of a revision. The first one holds the full text of head.
+    DeltaText(DeltaText),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum State {
+    Admin,
+    Deltas,
+    DeltaTextHead,
+    DeltaTexts,
+    Done,
+}
+
+/// Result of scanning the buffer for the end of the next item.
+enum Scan {
+    /// The item ends at the given position.
+    End(usize),
+    /// More input is needed to find the end of the item.
+    Incomplete,
+}
+
+/// Streaming parser of comma-v files.
+///
+/// The reader yields the admin part first, then each delta, then desc, and at last each deltatext.
+/// Only the item being parsed is kept in memory, so huge files can be processed with bounded memory.
+///
+/// Example:
+/// ```rust
+/// use rcs_parser::{Event, RcsReader};
+///
+/// let file = std::fs::File::open("examples/text1.txt,v").unwrap();
+/// for event in RcsReader::from_reader(file) {
+///     match event.unwrap() {
+///         Event::Delta(delta) => println!("{:?} {}", delta.num, delta.author),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub struct RcsReader<R> {
+    reader: R,
+    buf: String,
+    pos: usize,
+    eof: bool,
+    raw: bool,
+    state: State,
+}
+
+impl<R: Read> RcsReader<BufReader<R>> {
+    /// Creates a streaming parser over any reader.
+    pub fn from_reader(reader: R) -> Self {
+        RcsReader::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> RcsReader<R> {
+    /// Creates a streaming parser over a buffered reader.
+    pub fn new(reader: R) -> Self {
+        RcsReader {
+            reader,
+            buf: String::new(),
+            pos: 0,
+            eof: false,
+            raw: false,
+            state: State::Admin,
+        }
+    }
+
+    /// Reads the input as bytes, like [parse_rcs_bytes] does.
+    /// Files which are not valid UTF-8, e.g. binary files, can be read this way.
+    pub fn raw_bytes(mut self) -> Self {
+        self.raw = true;
+        self
+    }
+
+    /// Reads more input into the buffer, if the reader is not exhausted.
+    ///
+    /// At least [CHUNK_SIZE] bytes are read, or as many as the buffer already holds, so the buffer grows
+    /// geometrically. An item is rescanned after each fill, so a long `@` string is scanned a logarithmic
+    /// number of times instead of once per chunk.
+    fn fill(&mut self) -> Result<(), Error> {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        let target = self.buf.len() + self.buf.len().max(CHUNK_SIZE);
+        let mut bytes = Vec::new();
+        while !self.eof && self.buf.len() < target {
+            let n = if self.raw {
+                bytes.clear();
+                let n = self.reader.read_until(b'\n', &mut bytes)?;
+                self.buf.push_str(&bytes_to_text(&bytes));
+                n
+            } else {
+                self.reader.read_line(&mut self.buf)?
+            };
+            if n == 0 {
+                self.eof = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the end of the next item and returns its text, reading more input when needed.
+    fn next_item(&mut self, scan: fn(&str, usize) -> Scan) -> Result<Option<&str>, Error> {
+        loop {
+            let start = skip_ws(&self.buf, self.pos);
+            if start == self.buf.len() && self.eof {
+                return Ok(None);
+            }
+            if let Scan::End(end) = scan(&self.buf, start) {
+                let item = &self.buf[self.pos..end];
+                self.pos = end;
+                return Ok(Some(item));
+            }
+            if self.eof {
+                return Err(Error::Parse("unexpected end of input".to_string()));
+            }
+            self.fill()?;
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        match self.state {
+            State::Admin => {
+                let item = self.next_item(scan_statements)?.unwrap_or("");
+                let rcsdata = parse_all(item, parse_admin)?;
+                self.state = State::Deltas;
+                Ok(Some(Event::Admin(rcsdata)))
+            }
+            State::Deltas => {
+                if self.starts_with_desc()? {
+                    let item = self.next_item(scan_desc)?.unwrap_or("");
+                    let desc = parse_all(item, parse_desc)?;
+                    self.state = State::DeltaTextHead;
+                    Ok(Some(Event::Desc(desc)))
+                } else {
+                    let item = self.next_item(scan_statements)?.unwrap_or("");
+                    Ok(Some(Event::Delta(parse_all(item, parse_delta)?)))
+                }
+            }
+            State::DeltaTextHead | State::DeltaTexts => {
+                let head = self.state == State::DeltaTextHead;
+                let deltatext = match self.next_item(scan_deltatext)? {
+                    None => None,
+                    Some(item) if head => Some(parse_all(item.trim_start(), parse_deltatext_head)?),
+                    Some(item) => Some(parse_all(item.trim_start(), parse_deltatext)?),
+                };
+                match deltatext {
+                    None => {
+                        self.state = State::Done;
+                        Ok(None)
+                    }
+                    Some(deltatext) => {
+                        self.state = State::DeltaTexts;
+                        Ok(Some(Event::DeltaText(deltatext)))
+                    }
+                }
+            }
+            State::Done => Ok(None),
+        }
+    }
+
+    /// Tells if the next word in the input is `desc`.
+    fn starts_with_desc(&mut self) -> Result<bool, Error> {
+        loop {
+            let start = skip_ws(&self.buf, self.pos);
+            if let Scan::End(end) = scan_word(&self.buf, start) {
+                return Ok(&self.buf[start..end] == "desc");
+            }
+            if self.eof {
+                return Err(Error::Parse("unexpected end of input".to_string()));
+            }
+            self.fill()?;
+        }
+    }
+}
+
+impl<R: BufRead> RcsReader<R> {
+    /// Reads the whole input, calling the visitor for each part.
+    pub fn visit<V: Visitor + ?Sized>(self, visitor: &mut V) -> Result<(), Error> {
+        for event in self {
+            match event? {
+                Event::Admin(admin) => {
+                    for (name, num) in &admin.symbols {
+                        visitor.on_symbol(name, num);
+                    }
+                    visitor.on_admin(admin);
+                }
+                Event::Delta(delta) => visitor.on_delta(delta),
+                Event::Desc(desc) => visitor.on_desc(desc),
+                Event::DeltaText(deltatext) => visit_deltatext(visitor, deltatext),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for RcsReader<R> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Runs a parser on a whole item. Only whitespaces may remain after the parsed value.
+fn parse_all<'a, O>(
+    input: &'a str,
+    mut parser: impl FnMut(&'a str) -> nom::IResult<&'a str, O, nom::error::VerboseError<&'a str>>,
+) -> Result<O, Error> {
+    let (rest, value) = parser(input).map_err(Error::from_nom)?;
+    if rest.trim().is_empty() {
+        Ok(value)
+    } else {
+        Err(Error::Parse(format!(
+            "unexpected input '{}'",
+            rest.trim_start().lines().next().unwrap_or("")
+        )))
+    }
+}
+
+fn skip_ws(s: &str, i: usize) -> usize {
+    s[i..]
+        .find(|c: char| !c.is_ascii_whitespace())
+        .map_or(s.len(), |n| i + n)
+}
+
+fn is_num_word(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Scans a word, i.e. anything up to a whitespace or a special delimiter.
+fn scan_word(s: &str, i: usize) -> Scan {
+    match s[i..].find(|c: char| c.is_ascii_whitespace() || c == ';' || c == ':' || c == '@') {
+        Some(n) => Scan::End(i + n),
+        None => Scan::Incomplete,
+    }
+}
+
+/// Scans an `@` delimited string starting at `i`.
+fn scan_string(s: &str, i: usize) -> Scan {
+    let bytes = s.as_bytes();
+    let mut j = i + 1;
+    while let Some(n) = s[j..].find('@') {
+        j += n + 1;
+        match bytes.get(j) {
+            Some(b'@') => j += 1,
+            Some(_) => return Scan::End(j),
+            None => return Scan::Incomplete,
+        }
+    }
+    Scan::Incomplete
+}
+
+/// Scans a statement ending with `;`, which may contain strings.
+fn scan_statement(s: &str, mut i: usize) -> Scan {
+    loop {
+        i = skip_ws(s, i);
+        match s.as_bytes().get(i) {
+            None => return Scan::Incomplete,
+            Some(b';') => return Scan::End(i + 1),
+            Some(b'@') => match scan_string(s, i) {
+                Scan::End(end) => i = end,
+                incomplete => return incomplete,
+            },
+            Some(b':') => i += 1,
+            Some(_) => match scan_word(s, i) {
+                Scan::End(end) => i = end,
+                incomplete => return incomplete,
+            },
+        }
+    }
+}
+
+/// Scans the statements of admin or of a delta. They end when a new delta or desc begins.
+fn scan_statements(s: &str, i: usize) -> Scan {
+    let mut end = i;
+    loop {
+        let start = skip_ws(s, end);
+        let word_end = match scan_word(s, start) {
+            Scan::End(word_end) => word_end,
+            incomplete => return incomplete,
+        };
+        let word = &s[start..word_end];
+        if end > i && (is_num_word(word) || word == "desc") {
+            return Scan::End(end);
+        }
+        end = match scan_statement(s, word_end) {
+            Scan::End(end) => end,
+            incomplete => return incomplete,
+        };
+    }
+}
+
+fn scan_desc(s: &str, i: usize) -> Scan {
+    let start = skip_ws(s, i + "desc".len());
+    if start == s.len() {
+        return Scan::Incomplete;
+    }
+    scan_string(s, start)
+}
+
+/// Scans a deltatext: a num, a log string, optional newphrases and a text string.
+fn scan_deltatext(s: &str, i: usize) -> Scan {
+    let mut end = match scan_word(s, i) {
+        Scan::End(end) => end,
+        incomplete => return incomplete,
+    };
+    loop {
+        let start = skip_ws(s, end);
+        let word_end = match scan_word(s, start) {
+            Scan::End(word_end) => word_end,
+            incomplete => return incomplete,
+        };
+        let word = &s[start..word_end];
+        if word == "log" || word == "text" {
+            let string_start = skip_ws(s, word_end);
+            if string_start == s.len() {
+                return Scan::Incomplete;
+            }
+            end = match scan_string(s, string_start) {
+                Scan::End(end) if word == "text" => return Scan::End(end),
+                Scan::End(end) => end,
+                incomplete => return incomplete,
+            };
+        } else {
+            end = match scan_statement(s, word_end) {
+                Scan::End(end) => end,
+                incomplete => return incomplete,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn read_events() {
+        let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+        let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+        let reader = BufReader::with_capacity(16, contents.as_bytes());
+        let events: Vec<Event> = RcsReader::new(reader).map(|e| e.unwrap()).collect();
+        assert_eq!(16, events.len());
+        match &events[0] {
+            Event::Admin(admin) => {
+                assert_eq!(rcs.head, admin.head);
+                assert_eq!(rcs.symbols, admin.symbols);
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+        for event in &events[1..8] {
+            match event {
+                Event::Delta(d) => assert_eq!(rcs.deltas[&d.num].author, d.author),
+                e => panic!("unexpected event {:?}", e),
+            }
+        }
+        assert_eq!(Event::Desc(rcs.desc.clone()), events[8]);
+        for event in &events[9..] {
+            match event {
+                Event::DeltaText(t) => assert_eq!(rcs.deltas[&t.num].text, t.text),
+                e => panic!("unexpected event {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn read_truncated() {
+        let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+        let truncated = &contents[..contents.len() - 20];
+        let result: Result<Vec<Event>, Error> =
+            RcsReader::from_reader(truncated.as_bytes()).collect();
+        assert!(matches!(result, Err(Error::Parse(_))));
+    }
+
+    #[test]
+    fn read_long_text() {
+        let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+        let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+        let long = "a long line of the head revision\n".repeat(100_000);
+        let contents = contents.replacen("text\n@", &format!("text\n@{}", long), 1);
+        let head = RcsReader::from_reader(contents.as_bytes())
+            .filter_map(|e| match e.unwrap() {
+                Event::DeltaText(t) => Some(t),
+                _ => None,
+            })
+            .next()
+            .unwrap();
+        match (head.text, &rcs.deltas[&rcs.head].text) {
+            (Text::Head(text), Text::Head(original)) => assert_eq!(long + original, text),
+            e => panic!("unexpected texts {:?}", e),
+        }
+    }
+
+    #[test]
+    fn read_raw_bytes() {
+        let contents = std::fs::read("examples/text1.txt,v").unwrap();
+        let rcs = parse_rcs_bytes(&contents).unwrap();
+        let mut reader = RcsReader::from_reader(&contents[..]).raw_bytes();
+        match reader.next() {
+            Some(Ok(Event::Admin(admin))) => assert_eq!(rcs.head, admin.head),
+            e => panic!("unexpected event {:?}", e),
+        }
+        let texts = reader
+            .filter_map(|e| match e.unwrap() {
+                Event::DeltaText(t) => Some(t),
+                _ => None,
+            })
+            .count();
+        assert_eq!(rcs.deltas.len(), texts);
+    }
+
+    #[test]
+    fn scan_string() {
+        assert!(matches!(
+            super::scan_string("@ab@@c@ x", 0),
+            super::Scan::End(7)
+        ));
+        assert!(matches!(
+            super::scan_string("@ab@", 0),
+            super::Scan::Incomplete
+        ));
+        assert!(matches!(
+            super::scan_string("@ab@@", 0),
+            super::Scan::Incomplete
+        ));
+    }
+}
@@ -0,0 +1,538 @@
+use crate::date::{date_fields, unix_seconds};
+use crate::*;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::Path;
+
+/// Revisions of different files belong to the same commit if they are closer in time, in seconds.
+const COMMIT_WINDOW: i64 = 300;
+
+/// Exports comma-v files to a Subversion dump stream, which can be loaded by `svnadmin load`.
+///
+/// The repository layout is `trunk`, `branches` and `tags`. Trunk revisions go to `trunk`, revisions of
+/// branches to `branches/<name>`, where the name is the symbolic name of the branch, or
+/// `unlabeled-<number>` if it has none. Branches are copied from their branching points before their
+/// first commit, and tags are copied at the end of the history.
+///
+/// Revisions of different files are grouped into one commit if they have the same commit id, or the same
+/// author and log message and they were committed within five minutes.
+///
+/// Files of a repository are decoded by a [Decoder], so logs which are not valid UTF-8 reach `svn:log`
+/// converted, not as Latin-1 mojibake.
+///
+/// ```rust
+/// use rcs_parser::SvnExport;
+/// use std::path::Path;
+///
+/// let mut export = SvnExport::new();
+/// export.add_repository(Path::new("examples")).unwrap();
+/// let mut dump = Vec::new();
+/// let revisions = export.write(&mut dump).unwrap();
+/// assert!(dump.starts_with(b"SVN-fs-dump-format-version: 2\n"));
+/// assert!(revisions > 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SvnExport {
+    files: Vec<(String, RcsData)>,
+    authors: AuthorMap,
+    decoder: Decoder,
+    graft_vendor_branch: bool,
+}
+
+/// A revision of a file to export.
+#[derive(Debug)]
+struct Change {
+    date: [u32; 6],
+    file: usize,
+    num: Num,
+    location: String,
+}
+
+impl Default for SvnExport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SvnExport {
+    /// Creates an exporter without files. Vendor branches are grafted onto the trunk,
+    /// files are decoded by the default [Decoder].
+    pub fn new() -> Self {
+        SvnExport {
+            files: Vec::new(),
+            authors: AuthorMap::default(),
+            decoder: Decoder::default(),
+            graft_vendor_branch: true,
+        }
+    }
+
+    /// Sets the author map. `svn:author` is the full name of mapped authors, the login of the others.
+    pub fn authors(mut self, authors: AuthorMap) -> Self {
+        self.authors = authors;
+        self
+    }
+
+    /// Sets the decoder of the files added by [add_repository](SvnExport::add_repository).
+    pub fn decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Sets if vendor branch revisions are grafted onto the trunk, see [RcsData::trunk_history].
+    pub fn graft_vendor_branch(mut self, graft: bool) -> Self {
+        self.graft_vendor_branch = graft;
+        self
+    }
+
+    /// Adds a file. `path` is the path of the working file inside the repository, e.g. `src/main.c`.
+    ///
+    /// Files parsed from bytes should be decoded first, see [Decoder::decode_rcs].
+    pub fn add_file(&mut self, path: &Path, rcs: RcsData) {
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.files.push((path, rcs));
+    }
+
+    /// Adds all comma-v files of a repository. Their texts are decoded by the decoder of the exporter.
+    ///
+    /// A comma-v file which cannot be read or decoded is left out, and the others are added: the errors of such
+    /// files are returned, each error is an [Error::InFile]. Errors of listing the repository are returned.
+    pub fn add_repository(&mut self, repository: &Path) -> Result<Vec<Error>, Error> {
+        let mut failed = Vec::new();
+        for rcsfile in find_rcs_files(repository)? {
+            match self.decoder.read_rcs_file(&rcsfile) {
+                Ok((rcs, _)) => {
+                    let relative = rcsfile.strip_prefix(repository).unwrap_or(&rcsfile);
+                    self.add_file(&working_path(relative), rcs);
+                }
+                Err(e) => failed.push(e),
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Returns the location of a branch: `trunk` or `branches/<name>`.
+    fn location(rcs: &RcsData, branch: &Num) -> String {
+        match rcs
+            .symbols
+            .iter()
+            .find(|(_, num)| num.is_branch() && num.to_real_branch() == *branch)
+        {
+            Some((name, _)) => format!("branches/{}", name),
+            None => format!("branches/unlabeled-{}", branch),
+        }
+    }
+
+    /// Returns the location of a symbolic name: `tags/<name>` for tags, the location of the branch for branches.
+    fn symbol_location(rcs: &RcsData, name: &str, num: &Num) -> String {
+        if num.is_branch() {
+            Self::location(rcs, &num.to_real_branch())
+        } else {
+            format!("tags/{}", name)
+        }
+    }
+
+    /// Collects the revisions of all files, ordered by date.
+    fn changes(&self) -> Result<Vec<Change>, Error> {
+        let mut changes = Vec::new();
+        for (file, (path, rcs)) in self.files.iter().enumerate() {
+            let in_file = |e: Error| Error::InFile(path.into(), Box::new(e));
+            let trunk = rcs
+                .trunk_history(self.graft_vendor_branch)
+                .map_err(in_file)?;
+            let mut revisions: Vec<(Num, String)> = Vec::new();
+            for branch in rcs.branches() {
+                let location = Self::location(rcs, &branch);
+                for num in rcs.branch_revisions(&branch).map_err(in_file)? {
+                    if !trunk.contains(&num) {
+                        revisions.push((num, location.clone()));
+                    }
+                }
+            }
+            revisions.extend(trunk.into_iter().map(|num| (num, "trunk".to_string())));
+            changes.extend(revisions.into_iter().map(|(num, location)| Change {
+                date: date_fields(&rcs.deltas[&num].date),
+                file,
+                num,
+                location,
+            }));
+        }
+        changes.sort_by(|a, b| (a.date, a.file, &a.num).cmp(&(b.date, b.file, &b.num)));
+        Ok(changes)
+    }
+
+    /// Groups changes into commits.
+    fn commits(&self, changes: Vec<Change>) -> Vec<Vec<Change>> {
+        let mut commits: Vec<Vec<Change>> = Vec::new();
+        for change in changes {
+            let delta = &self.files[change.file].1.deltas[&change.num];
+            let joins = commits.last().is_some_and(|commit| {
+                let last = &commit[commit.len() - 1];
+                let first = &self.files[commit[0].file].1.deltas[&commit[0].num];
+                let same = match (&delta.commitid, &first.commitid) {
+                    (Some(a), Some(b)) => a == b,
+                    (None, None) => {
+                        delta.author == first.author
+                            && delta.log == first.log
+                            && change.location == commit[0].location
+                    }
+                    _ => false,
+                };
+                same && unix_seconds(&change.date) - unix_seconds(&last.date) <= COMMIT_WINDOW
+                    && commit.iter().all(|c| c.file != change.file)
+            });
+            match commits.last_mut() {
+                Some(commit) if joins => commit.push(change),
+                _ => commits.push(vec![change]),
+            }
+        }
+        commits
+    }
+
+    /// Writes the dump stream. Returns the number of Subversion revisions.
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<u32, Error> {
+        let commits = self.commits(self.changes()?);
+        let mut dump = Dump {
+            out,
+            revision: None,
+            paths: BTreeSet::new(),
+            exported: HashMap::new(),
+        };
+        let first_date = commits
+            .first()
+            .map(|c| c[0].date)
+            .unwrap_or([1970, 1, 1, 0, 0, 0]);
+        writeln!(dump.out, "SVN-fs-dump-format-version: 2\n")?;
+        dump.begin_revision(&[("svn:date", &svn_date(&first_date))])?;
+        dump.begin_revision(&[
+            ("svn:date", &svn_date(&first_date)),
+            ("svn:log", "Standard project directories."),
+        ])?;
+        for dir in ["trunk", "branches", "tags"].iter() {
+            dump.ensure_dirs(&format!("{}/", dir))?;
+        }
+
+        let mut created = BTreeSet::new();
+        for commit in commits.iter() {
+            for change in commit {
+                if change.location != "trunk" && created.insert(change.location.clone()) {
+                    self.copy_symbol(&mut dump, &change.location, &change.date)?;
+                }
+            }
+            let (_, first_rcs) = &self.files[commit[0].file];
+            let first = &first_rcs.deltas[&commit[0].num];
+            let date = svn_date(&commit[commit.len() - 1].date);
+            let author = match self.authors.get(&first.author) {
+                Some(identity) => identity.name.clone(),
+                None => first.author.clone(),
+            };
+            dump.begin_revision(&[
+                ("svn:author", &author),
+                ("svn:date", &date),
+                ("svn:log", &first.log),
+            ])?;
+            for change in commit {
+                let (path, rcs) = &self.files[change.file];
+                let node_path = format!("{}/{}", change.location, path);
+                if rcs.is_dead(&change.num) {
+                    dump.delete(&node_path)?;
+                } else {
+                    let content = rcs
+                        .checkout_bytes(&change.num)
+                        .map_err(|e| Error::InFile(path.into(), Box::new(e)))?;
+                    dump.file(&node_path, &node_properties(rcs), &content)?;
+                    dump.exported.insert(
+                        (change.file, change.num.clone()),
+                        (dump.revision.unwrap_or(0), node_path),
+                    );
+                }
+            }
+        }
+
+        let last_date = commits
+            .last()
+            .map(|c| c[c.len() - 1].date)
+            .unwrap_or(first_date);
+        let mut pending = BTreeSet::new();
+        for (_, rcs) in self.files.iter() {
+            for (name, num) in rcs.symbols.iter() {
+                pending.insert(Self::symbol_location(rcs, name, num));
+            }
+        }
+        for location in pending {
+            if created.insert(location.clone()) {
+                self.copy_symbol(&mut dump, &location, &last_date)?;
+            }
+        }
+        Ok(dump.revision.unwrap_or(0))
+    }
+
+    /// Writes a revision which creates a branch or a tag by copying the files from their revisions.
+    fn copy_symbol<W: Write>(
+        &self,
+        dump: &mut Dump<W>,
+        location: &str,
+        date: &[u32; 6],
+    ) -> Result<(), Error> {
+        let mut copies = Vec::new();
+        for (file, (path, rcs)) in self.files.iter().enumerate() {
+            let tagged = rcs.symbols.iter().find_map(|(name, num)| {
+                if Self::symbol_location(rcs, name, num) != location {
+                    None
+                } else if num.is_branch() {
+                    Some(num.to_real_branch().get_branching_point())
+                } else {
+                    Some(num.clone())
+                }
+            });
+            let source = tagged.or_else(|| {
+                rcs.branches()
+                    .into_iter()
+                    .find(|branch| Self::location(rcs, branch) == location)
+                    .map(|branch| branch.get_branching_point())
+            });
+            let exported = source.and_then(|source| {
+                match dump.exported.get(&(file, source.clone())) {
+                    Some(exported) => Some(exported.clone()),
+                    // 1.1 may be left out as an import artifact, then the branch starts from 1.1.1.1
+                    None if source == num![1, 1] => {
+                        let mut first = rcs.vendor_branch()?;
+                        first.numbers.push(1);
+                        dump.exported.get(&(file, first)).cloned()
+                    }
+                    None => None,
+                }
+            });
+            if let Some(source) = exported {
+                copies.push((format!("{}/{}", location, path), source));
+            }
+        }
+        let kind = if location.starts_with("tags/") {
+            "tag"
+        } else {
+            "branch"
+        };
+        let name = &location[location.find('/').unwrap_or(0) + 1..];
+        dump.begin_revision(&[
+            ("svn:date", &svn_date(date)),
+            ("svn:log", &format!("Create {} '{}'.", kind, name)),
+        ])?;
+        dump.ensure_dirs(&format!("{}/", location))?;
+        for (path, (revision, from)) in copies {
+            dump.copy(&path, revision, &from)?;
+        }
+        Ok(())
+    }
+}
+
+/// Node properties of the files: `svn:mime-type` for binary files, `svn:keywords` if keywords are expanded.
+fn node_properties(rcs: &RcsData) -> Vec<(&'static str, &'static str)> {
+    match rcs.expand_mode() {
+        Expand::Binary => vec![("svn:mime-type", "application/octet-stream")],
+        Expand::KeyValue | Expand::KeyValueLocker => {
+            vec![("svn:keywords", "Author Date Id Revision")]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Formats a date as Subversion stores it: `2021-04-10T09:38:42.000000Z`.
+fn svn_date(date: &[u32; 6]) -> String {
+    let [year, month, day, hour, min, sec] = date;
+    format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.000000Z",
+        year, month, day, hour, min, sec
+    )
+}
+
+/// Serializes properties: `K <len>\n<key>\nV <len>\n<value>\n` for each, closed by `PROPS-END\n`.
+fn properties(props: &[(&str, &str)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (key, value) in props {
+        bytes.extend(format!("K {}\n{}\nV {}\n{}\n", key.len(), key, value.len(), value).bytes());
+    }
+    bytes.extend(b"PROPS-END\n");
+    bytes
+}
+
+/// The state of a dump stream being written.
+struct Dump<'a, W: Write> {
+    out: &'a mut W,
+    /// The number of the current revision, `None` before revision 0.
+    revision: Option<u32>,
+    /// Existing files and directories. Directories end with `/`.
+    paths: BTreeSet<String>,
+    /// Subversion revisions and paths of the exported file revisions.
+    exported: HashMap<(usize, Num), (u32, String)>,
+}
+
+impl<'a, W: Write> Dump<'a, W> {
+    fn begin_revision(&mut self, props: &[(&str, &str)]) -> Result<(), Error> {
+        let revision = self.revision.map_or(0, |r| r + 1);
+        self.revision = Some(revision);
+        let props = properties(props);
+        write!(
+            self.out,
+            "Revision-number: {}\nProp-content-length: {}\nContent-length: {}\n\n",
+            revision,
+            props.len(),
+            props.len()
+        )?;
+        self.out.write_all(&props)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    /// Adds the missing parent directories of a path. A path ending with `/` is a directory itself.
+    fn ensure_dirs(&mut self, path: &str) -> Result<(), Error> {
+        let mut end = 0;
+        while let Some(pos) = path[end..].find('/') {
+            end += pos + 1;
+            let dir = &path[..end];
+            if self.paths.insert(dir.to_string()) {
+                let props = properties(&[]);
+                write!(
+                    self.out,
+                    "Node-path: {}\nNode-kind: dir\nNode-action: add\nProp-content-length: {}\nContent-length: {}\n\n",
+                    &dir[..dir.len() - 1],
+                    props.len(),
+                    props.len()
+                )?;
+                self.out.write_all(&props)?;
+                writeln!(self.out, "\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds or changes a file.
+    fn file(&mut self, path: &str, props: &[(&str, &str)], content: &[u8]) -> Result<(), Error> {
+        self.ensure_dirs(path)?;
+        let action = if self.paths.insert(path.to_string()) {
+            "add"
+        } else {
+            "change"
+        };
+        let props = properties(props);
+        write!(
+            self.out,
+            "Node-path: {}\nNode-kind: file\nNode-action: {}\nProp-content-length: {}\nText-content-length: {}\nContent-length: {}\n\n",
+            path,
+            action,
+            props.len(),
+            content.len(),
+            props.len() + content.len()
+        )?;
+        self.out.write_all(&props)?;
+        self.out.write_all(content)?;
+        writeln!(self.out, "\n")?;
+        Ok(())
+    }
+
+    /// Copies a file from an earlier revision.
+    fn copy(&mut self, path: &str, revision: u32, from: &str) -> Result<(), Error> {
+        self.ensure_dirs(path)?;
+        self.paths.insert(path.to_string());
+        write!(
+            self.out,
+            "Node-path: {}\nNode-kind: file\nNode-action: add\nNode-copyfrom-rev: {}\nNode-copyfrom-path: {}\n\n\n",
+            path, revision, from
+        )?;
+        Ok(())
+    }
+
+    /// Deletes a file if it exists.
+    fn delete(&mut self, path: &str) -> Result<(), Error> {
+        if self.paths.remove(path) {
+            write!(self.out, "Node-path: {}\nNode-action: delete\n\n\n", path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{broken_repository, parse_example};
+    use crate::*;
+    use std::path::Path;
+
+    fn dump(export: &SvnExport) -> String {
+        let mut out = Vec::new();
+        export.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn svn_date() {
+        assert_eq!(
+            "2021-04-10T09:38:42.000000Z",
+            super::svn_date(&[2021, 4, 10, 9, 38, 42])
+        );
+    }
+
+    #[test]
+    fn properties() {
+        assert_eq!(
+            "K 10\nsvn:author\nV 6\ndseres\nPROPS-END\n",
+            String::from_utf8(super::properties(&[("svn:author", "dseres")])).unwrap()
+        );
+    }
+
+    #[test]
+    fn write() {
+        let rcs = parse_example("text1.txt,v");
+        let mut export = SvnExport::new();
+        export.add_file(Path::new("doc/text1.txt"), rcs);
+        let mut authors = AuthorMap::default();
+        authors.insert("dseres", "David Seres", "dseres001@gmail.com");
+        let dump = dump(&export.authors(authors));
+        assert!(dump.contains("Node-path: trunk/doc\nNode-kind: dir\nNode-action: add\n"));
+        assert!(
+            dump.contains("Node-path: trunk/doc/text1.txt\nNode-kind: file\nNode-action: add\n")
+        );
+        assert!(dump.contains("V 11\nDavid Seres\n"));
+        assert!(dump.contains("K 12\nsvn:keywords\n"));
+        assert!(dump.contains("Node-path: branches/unlabeled-1.2.2/doc/text1.txt\nNode-kind: file\nNode-action: add\nNode-copyfrom-rev: "));
+        assert!(dump.contains("Node-path: tags/Fix2/doc/text1.txt\nNode-kind: file\nNode-action: add\nNode-copyfrom-rev: "));
+        assert!(dump.contains("Node-copyfrom-path: branches/unlabeled-1.2.2/doc/text1.txt\n"));
+        // 2 initial revisions, 3 trunk revisions, 2 branches created and 4 branch revisions, 4 tags
+        assert_eq!(15, dump.matches("Revision-number: ").count());
+    }
+
+    #[test]
+    fn dead_and_vendor() {
+        let mut export = SvnExport::new();
+        for file in ["removed.c", "vendor.c"].iter() {
+            let rcs = parse_example(&format!("{},v", file));
+            export.add_file(Path::new(file), rcs);
+        }
+        let dump = dump(&export);
+        assert!(dump.contains("Node-path: trunk/removed.c\nNode-action: delete\n"));
+        assert!(
+            dump.contains("Node-path: branches/B/removed.c\nNode-kind: file\nNode-action: add\n")
+        );
+        assert!(dump.contains("Node-path: trunk/vendor.c\nNode-kind: file\nNode-action: change\n"));
+        assert!(dump.contains("Node-path: tags/RELEASE_1/vendor.c\n"));
+    }
+
+    #[test]
+    fn add_repository() {
+        let dir = broken_repository("svn", "vendor.c,v");
+        let mut contents = std::fs::read("examples/vendoronly.c,v").unwrap();
+        let pos = contents.windows(5).position(|w| w == b"log\n@").unwrap() + 5;
+        contents.splice(pos..pos, b"\x93quoted\x94 ".iter().cloned());
+        std::fs::write(dir.join("a.c,v"), contents).unwrap();
+        let mut export = SvnExport::new();
+        let failed = export.add_repository(&dir).unwrap();
+        assert_eq!(1, failed.len());
+        assert!(matches!(&failed[0], Error::InFile(path, _) if path.ends_with("broken.c,v")));
+        let dump = dump(&export);
+        assert!(dump.contains("Node-path: trunk/vendor.c\n"));
+        assert!(dump.contains("\u{201c}quoted\u{201d} "));
+        assert!(!dump.contains('\u{93}'));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
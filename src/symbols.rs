@@ -0,0 +1,331 @@
+use crate::parsers::is_idchar;
+use crate::*;
+
+/// Kind of a symbolic name.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SymbolKind {
+    /// The name is a tag of a revision.
+    Tag,
+    /// The name is a branch, given by a CVS magic branch number (`1.2.0.4`) or by a branch number.
+    Branch,
+    /// The name is the vendor branch of `cvs import`, see [vendor_branch](RcsData::vendor_branch).
+    VendorBranch,
+}
+
+impl SymbolKind {
+    /// Classifies a num of a symbolic name as a tag or a branch.
+    ///
+    /// A num alone does not tell if a branch is a vendor branch,
+    /// [classify_symbols](RcsData::classify_symbols) recognizes them.
+    ///
+    /// ```rust
+    /// use rcs_parser::{num, Num, SymbolKind};
+    /// assert_eq!(SymbolKind::Tag, SymbolKind::of(&num![1, 2]));
+    /// assert_eq!(SymbolKind::Branch, SymbolKind::of(&num![1, 2, 0, 4]));
+    /// assert_eq!(SymbolKind::Branch, SymbolKind::of(&num![1, 2, 1]));
+    /// ```
+    pub fn of(num: &Num) -> SymbolKind {
+        if num.is_branch() {
+            SymbolKind::Branch
+        } else {
+            SymbolKind::Tag
+        }
+    }
+}
+
+impl RcsData {
+    /// Returns the symbolic names with their kind, in the order of the file.
+    ///
+    /// A branch is a vendor branch if it is the [vendor_branch](RcsData::vendor_branch) of the file.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num, SymbolKind};
+    ///
+    /// let contents = std::fs::read_to_string("examples/vendor.c,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(
+    ///     vec![
+    ///         ("RELEASE_2", &num![1, 1, 1, 2], SymbolKind::Tag),
+    ///         ("RELEASE_1", &num![1, 1, 1, 1], SymbolKind::Tag),
+    ///         ("VENDOR", &num![1, 1, 1], SymbolKind::VendorBranch)
+    ///     ],
+    ///     rcs.classify_symbols()
+    /// );
+    /// ```
+    pub fn classify_symbols(&self) -> Vec<(&str, &Num, SymbolKind)> {
+        let vendor = self.vendor_branch();
+        self.symbols
+            .iter()
+            .map(|(name, num)| {
+                let kind = match SymbolKind::of(num) {
+                    SymbolKind::Branch if Some(num.to_real_branch()) == vendor => {
+                        SymbolKind::VendorBranch
+                    }
+                    kind => kind,
+                };
+                (name.as_str(), num, kind)
+            })
+            .collect()
+    }
+
+    /// Returns the num of a symbolic name as it is stored.
+    pub fn get_symbol(&self, name: &str) -> Result<&Num, Error> {
+        self.symbols
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, num)| num)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_string()))
+    }
+
+    /// Resolves a symbolic name to a revision.
+    ///
+    /// A tag gives its revision. A branch gives its newest revision, or its branching point
+    /// if nothing was committed on the branch yet. Magic branch numbers are converted to real branches.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(num![1, 2, 2, 3], rcs.resolve_symbol("Fix2").unwrap());
+    /// ```
+    pub fn resolve_symbol(&self, name: &str) -> Result<Num, Error> {
+        let num = self.get_symbol(name)?;
+        self.resolve_num(num)
+    }
+
+    /// Resolves a revision given by a number or by a symbolic name, as the `-r` option of RCS commands.
+    ///
+    /// A branch number gives the newest revision of the branch.
+    pub fn resolve_revision(&self, rev: &str) -> Result<Num, Error> {
+        match rev.parse::<Num>() {
+            Ok(num) => self.resolve_num(&num),
+            Err(_) => self.resolve_symbol(rev),
+        }
+    }
+
+    /// Returns the num a new symbolic name points to, as `rcs -n` takes it.
+    ///
+    /// An empty revision means the newest revision of the default branch. A symbolic name gives its num as it
+    /// is stored. A branch must have an existing branching point, a revision must exist.
+    fn symbol_target(&self, rev: &str) -> Result<Num, Error> {
+        if rev.is_empty() {
+            return self.default_head();
+        }
+        let num = match rev.parse::<Num>() {
+            Ok(num) => num,
+            Err(_) => return self.get_symbol(rev).cloned(),
+        };
+        if num.is_branch() && num.numbers.len() > 1 {
+            self.get_delta(&num.get_branching_point())?;
+        } else {
+            self.get_delta(&num)?;
+        }
+        Ok(num)
+    }
+
+    /// Adds a symbolic name to a revision or a branch, like `rcs -nname:rev`, or like `rcs -Nname:rev` if `force` is set.
+    ///
+    /// The revision is given by a number or by a symbolic name. An empty revision means the newest revision
+    /// of the default branch. An existing name is an error unless `force` is set; then it is moved.
+    /// New names are added to the front of the list, as RCS does.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+    /// let (_, mut rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// rcs.add_symbol("v1_2", "Fix1", false).unwrap();
+    /// assert_eq!(&num![1, 2, 1, 1], rcs.get_symbol("v1_2").unwrap());
+    /// assert!(rcs.add_symbol("v1_2", "2.1", false).is_err());
+    /// rcs.add_symbol("v1_2", "2.1", true).unwrap();
+    /// assert_eq!(&num![2, 1], rcs.get_symbol("v1_2").unwrap());
+    /// ```
+    pub fn add_symbol(&mut self, name: &str, rev: &str, force: bool) -> Result<(), Error> {
+        validate_symbol(name)?;
+        let target = self.symbol_target(rev)?;
+        match self.symbols.iter_mut().find(|(n, _)| n == name) {
+            Some((_, num)) if force => *num = target,
+            Some(_) => return Err(Error::SymbolExists(name.to_string())),
+            None => self.symbols.insert(0, (name.to_string(), target)),
+        }
+        Ok(())
+    }
+
+    /// Moves an existing symbolic name to another revision or branch, like `rcs -Nname:rev`.
+    pub fn move_symbol(&mut self, name: &str, rev: &str) -> Result<(), Error> {
+        self.get_symbol(name)?;
+        self.add_symbol(name, rev, true)
+    }
+
+    /// Renames a symbolic name, keeping its position. An existing new name is an error unless `force` is set;
+    /// then it is deleted.
+    pub fn rename_symbol(&mut self, name: &str, new_name: &str, force: bool) -> Result<(), Error> {
+        validate_symbol(new_name)?;
+        self.get_symbol(name)?;
+        if name == new_name {
+            return Ok(());
+        }
+        if self.get_symbol(new_name).is_ok() {
+            if !force {
+                return Err(Error::SymbolExists(new_name.to_string()));
+            }
+            self.delete_symbol(new_name)?;
+        }
+        for (n, _) in self.symbols.iter_mut().filter(|(n, _)| n == name) {
+            *n = new_name.to_string();
+        }
+        Ok(())
+    }
+
+    /// Deletes a symbolic name, like `rcs -nname`. Returns the num it pointed to.
+    pub fn delete_symbol(&mut self, name: &str) -> Result<Num, Error> {
+        let pos = self
+            .symbols
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_string()))?;
+        Ok(self.symbols.remove(pos).1)
+    }
+
+    fn resolve_num(&self, num: &Num) -> Result<Num, Error> {
+        if num.is_branch() {
+            self.branch_tip(&num.to_real_branch())
+        } else {
+            self.get_delta(num).map(|d| d.num.clone())
+        }
+    }
+}
+
+/// Checks a symbolic name: it consists of idchars, and it has at least one which is not a digit.
+fn validate_symbol(name: &str) -> Result<(), Error> {
+    if name.chars().all(is_idchar) && !name.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(Error::InvalidSymbol(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    fn cvs_file() -> RcsData {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.symbols
+            .push((String::from("Fix1_branch"), num![1, 2, 0, 1]));
+        rcs.symbols
+            .push((String::from("Fix2_branch"), num![1, 2, 0, 2]));
+        rcs.symbols.push((String::from("empty"), num![2, 1, 0, 2]));
+        rcs.symbols.push((String::from("vendor"), num![1, 1, 1]));
+        rcs.symbols.push((String::from("broken"), num![1, 5, 0, 2]));
+        rcs
+    }
+
+    #[test]
+    fn classify_symbols() {
+        let rcs = cvs_file();
+        let kinds: Vec<SymbolKind> = rcs.classify_symbols().iter().map(|s| s.2).collect();
+        assert_eq!(
+            vec![
+                SymbolKind::Tag,
+                SymbolKind::Tag,
+                SymbolKind::Tag,
+                SymbolKind::Tag,
+                SymbolKind::Branch,
+                SymbolKind::Branch,
+                SymbolKind::Branch,
+                SymbolKind::Branch,
+                SymbolKind::Branch
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn resolve_symbol() {
+        let rcs = cvs_file();
+        assert_eq!(num![1, 2], rcs.resolve_symbol("v1_1").unwrap());
+        assert_eq!(num![1, 2, 1, 1], rcs.resolve_symbol("Fix1_branch").unwrap());
+        assert_eq!(num![1, 2, 2, 3], rcs.resolve_symbol("Fix2_branch").unwrap());
+        assert_eq!(num![2, 1], rcs.resolve_symbol("empty").unwrap());
+        assert_eq!(num![1, 1], rcs.resolve_symbol("vendor").unwrap());
+        assert!(matches!(
+            rcs.resolve_symbol("broken"),
+            Err(Error::UnknownRevision(_))
+        ));
+        assert!(matches!(
+            rcs.resolve_symbol("nothing"),
+            Err(Error::UnknownSymbol(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_revision() {
+        let rcs = cvs_file();
+        assert_eq!(num![1, 2, 2, 3], rcs.resolve_revision("1.2.2").unwrap());
+        assert_eq!(num![1, 2, 2, 3], rcs.resolve_revision("1.2.0.2").unwrap());
+        assert_eq!(num![1, 1], rcs.resolve_revision("1.1").unwrap());
+        assert_eq!(num![2, 1], rcs.resolve_revision("v2_1").unwrap());
+        assert!(rcs.resolve_revision("1.5").is_err());
+    }
+
+    #[test]
+    fn add_symbol() {
+        let mut rcs = cvs_file();
+        rcs.add_symbol("head", "", false).unwrap();
+        assert_eq!(("head".to_string(), num![2, 1]), rcs.symbols[0]);
+        rcs.add_symbol("br", "1.2.4", false).unwrap();
+        assert_eq!(&num![1, 2, 4], rcs.get_symbol("br").unwrap());
+        rcs.add_symbol("magic", "1.2.0.6", false).unwrap();
+        assert!(matches!(
+            rcs.add_symbol("v1_1", "1.1", false),
+            Err(Error::SymbolExists(_))
+        ));
+        assert!(matches!(
+            rcs.add_symbol("bad.name", "1.1", false),
+            Err(Error::InvalidSymbol(_))
+        ));
+        assert!(matches!(
+            rcs.add_symbol("123", "1.1", false),
+            Err(Error::InvalidSymbol(_))
+        ));
+        assert!(matches!(
+            rcs.add_symbol("new", "1.7", false),
+            Err(Error::UnknownRevision(_))
+        ));
+        assert!(matches!(
+            rcs.add_symbol("new", "1.7.2", false),
+            Err(Error::UnknownRevision(_))
+        ));
+        assert!(matches!(
+            rcs.add_symbol("new", "nothing", false),
+            Err(Error::UnknownSymbol(_))
+        ));
+    }
+
+    #[test]
+    fn move_rename_delete_symbol() {
+        let mut rcs = cvs_file();
+        rcs.move_symbol("v1_1", "1.1").unwrap();
+        assert_eq!(&num![1, 1], rcs.get_symbol("v1_1").unwrap());
+        assert!(rcs.move_symbol("nothing", "1.1").is_err());
+
+        let pos = rcs.symbols.iter().position(|(n, _)| n == "v1_1").unwrap();
+        rcs.rename_symbol("v1_1", "v1_0", false).unwrap();
+        assert_eq!("v1_0", rcs.symbols[pos].0);
+        assert!(matches!(
+            rcs.rename_symbol("v1_0", "v2_1", false),
+            Err(Error::SymbolExists(_))
+        ));
+        rcs.rename_symbol("v1_0", "v2_1", true).unwrap();
+        assert_eq!(&num![1, 1], rcs.get_symbol("v2_1").unwrap());
+
+        assert_eq!(num![1, 1], rcs.delete_symbol("v2_1").unwrap());
+        assert!(matches!(
+            rcs.delete_symbol("v2_1"),
+            Err(Error::UnknownSymbol(_))
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::DiffCommand;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A changed region between two texts: lines `old` of the old text are replaced by lines `new` of the new text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Computes the differences of two texts given by lines, with Myers' algorithm.
+///
+/// Hunks are ordered, and they neither overlap nor touch each other.
+pub(crate) fn diff<S: AsRef<str>>(old: &[S], new: &[S]) -> Vec<Hunk> {
+    let mut ids = HashMap::new();
+    let mut intern = |line: &S| {
+        let next = ids.len();
+        *ids.entry(line.as_ref().to_string()).or_insert(next)
+    };
+    let old: Vec<usize> = old.iter().map(&mut intern).collect();
+    let new: Vec<usize> = new.iter().map(&mut intern).collect();
+
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (x, y) in matches(a, b).into_iter().chain(Some((a.len(), b.len()))) {
+        if x > i || y > j {
+            hunks.push(Hunk {
+                old: prefix + i..prefix + x,
+                new: prefix + j..prefix + y,
+            });
+        }
+        i = x + 1;
+        j = y + 1;
+    }
+    hunks
+}
+
+/// Converts the differences of two texts to RCS diff commands, which turn the old lines to the new lines.
+pub(crate) fn to_diff_commands(old: &[String], new: &[String]) -> Vec<DiffCommand> {
+    let mut commands = Vec::new();
+    for hunk in diff(old, new) {
+        if !hunk.old.is_empty() {
+            commands.push(DiffCommand::Delete(
+                hunk.old.start as u32 + 1,
+                hunk.old.len() as u32,
+            ));
+        }
+        if !hunk.new.is_empty() {
+            commands.push(DiffCommand::Add(
+                hunk.old.end as u32,
+                new[hunk.new].to_vec(),
+            ));
+        }
+    }
+    commands
+}
+
+/// Returns the matching line pairs of a longest common subsequence, in order.
+///
+/// This is the linear space variant of Myers' algorithm: a point of an optimal edit path is found by searching
+/// from both ends at once, then the parts before and after it are solved recursively. Memory is linear in the
+/// length of the texts, independently of the number of differences.
+fn matches(a: &[usize], b: &[usize]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    collect_matches(a, b, 0, 0, &mut pairs);
+    pairs
+}
+
+/// Appends the matching line pairs of `a` and `b` to `pairs`, the lines being numbered from `x0` and `y0`.
+fn collect_matches(
+    a: &[usize],
+    b: &[usize],
+    x0: usize,
+    y0: usize,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b.iter()).take_while(|(p, q)| p == q).count();
+    pairs.extend((0..prefix).map(|i| (x0 + i, y0 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(p, q)| p == q)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let (x0, y0) = (x0 + prefix, y0 + prefix);
+    if !a.is_empty() && !b.is_empty() {
+        if let Some((x, y)) = split_point(a, b) {
+            collect_matches(&a[..x], &b[..y], x0, y0, pairs);
+            collect_matches(&a[x..], &b[y..], x0 + x, y0 + y, pairs);
+        }
+    }
+    pairs.extend((0..suffix).map(|i| (x0 + a.len() + i, y0 + b.len() + i)));
+}
+
+/// Finds a point of an optimal edit path of two texts, where the paths searched from the beginning and from
+/// the end overlap. `None` is returned if the texts have no common line.
+fn split_point(a: &[usize], b: &[usize]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let len = 2 * max_d + 2;
+    let mut forward = vec![-1isize; len as usize];
+    let mut backward = vec![-1isize; len as usize];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    // If delta is odd, the paths overlap while searching forwards, otherwise while searching backwards.
+    let front = delta % 2 != 0;
+    // Diagonals running off the grid are not searched any more.
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let i = (offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[i] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let j = offset + delta - k1;
+                if j >= 0 && j < len && backward[j as usize] != -1 && x1 >= n - backward[j as usize]
+                {
+                    return Some((x1 as usize, y1 as usize));
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let i = (offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && backward[i - 1] < backward[i + 1]) {
+                backward[i + 1]
+            } else {
+                backward[i - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[i] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let j = offset + delta - k2;
+                if j >= 0 && j < len && forward[j as usize] != -1 {
+                    let x1 = forward[j as usize];
+                    let y1 = offset + x1 - j;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::Hunk;
+
+    fn lines(text: &str) -> Vec<String> {
+        crate::split_lines(text)
+    }
+
+    #[test]
+    fn diff() {
+        assert_eq!(
+            Vec::<Hunk>::new(),
+            super::diff(&lines("a\nb\n"), &lines("a\nb\n"))
+        );
+        assert_eq!(
+            vec![Hunk {
+                old: 1..2,
+                new: 1..3
+            }],
+            super::diff(&lines("a\nb\nc\n"), &lines("a\nx\ny\nc\n"))
+        );
+        assert_eq!(
+            vec![
+                Hunk {
+                    old: 0..1,
+                    new: 0..0
+                },
+                Hunk {
+                    old: 3..3,
+                    new: 2..3
+                }
+            ],
+            super::diff(&lines("a\nb\nc\n"), &lines("b\nc\nd\n"))
+        );
+        assert_eq!(
+            vec![Hunk {
+                old: 0..0,
+                new: 0..2
+            }],
+            super::diff(&lines(""), &lines("a\nb\n"))
+        );
+    }
+
+    #[test]
+    fn to_diff_commands() {
+        for (old, new) in [
+            ("a\nb\nc\n", "a\nx\ny\nc\n"),
+            ("a\nb\nc\n", "b\nc\nd"),
+            ("", "a\nb\n"),
+            ("a\nb\n", ""),
+        ] {
+            let commands = super::to_diff_commands(&lines(old), &lines(new));
+            assert_eq!(
+                Some(lines(new)),
+                crate::checkout::apply_diff(lines(old), &commands)
+            );
+        }
+    }
+
+    /// Returns the length of a longest common subsequence by dynamic programming.
+    fn lcs_len(a: &[usize], b: &[usize]) -> usize {
+        let mut row = vec![0; b.len() + 1];
+        for x in a {
+            let mut diagonal = 0;
+            for (j, y) in b.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if x == y {
+                    diagonal + 1
+                } else {
+                    above.max(row[j])
+                };
+                diagonal = above;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    fn matches() {
+        let mut seed = 12345u64;
+        let mut random = |limit: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % limit) as usize
+        };
+        for _ in 0..500 {
+            let (n, m, alphabet) = (random(30), random(30), random(6) as u64 + 1);
+            let a: Vec<usize> = (0..n).map(|_| random(alphabet)).collect();
+            let b: Vec<usize> = (0..m).map(|_| random(alphabet)).collect();
+            let pairs = super::matches(&a, &b);
+            assert_eq!(lcs_len(&a, &b), pairs.len(), "{:?} {:?}", a, b);
+            for (x, y) in pairs.iter() {
+                assert_eq!(a[*x], b[*y]);
+            }
+            for pair in pairs.windows(2) {
+                assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{date::date_fields, *};
+
+impl RcsData {
+    /// Returns the revision checked out when no revision is given: the tip of the default branch,
+    /// or head if the default branch is not set.
+    ///
+    /// After `cvs import` the default branch is the vendor branch, so the effective head is the
+    /// newest vendor revision until the first commit on the trunk.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/vendoronly.c,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(num![1, 1], rcs.head);
+    /// assert_eq!(num![1, 1, 1, 2], rcs.default_head().unwrap());
+    /// ```
+    pub fn default_head(&self) -> Result<Num, Error> {
+        match &self.branch {
+            Some(branch) => self.branch_tip(branch),
+            None => Ok(self.head.clone()),
+        }
+    }
+
+    /// Returns the vendor branch created by `cvs import`.
+    ///
+    /// It is a branch of `1.1` whose first revision was imported together with `1.1`: `1.1` has the log
+    /// `Initial revision`, the same date and the same content. The default branch is preferred if it is one of them.
+    /// Ordinary branches, e.g. the first branch `1.1.1` created by RCS or a default branch set by `rcs -b`,
+    /// are not vendor branches.
+    pub fn vendor_branch(&self) -> Option<Num> {
+        let imported: Vec<Num> = self
+            .deltas
+            .get(&num![1, 1])?
+            .branches
+            .iter()
+            .filter(|first| self.is_import_of(first))
+            .map(Num::get_branch)
+            .collect();
+        match &self.branch {
+            Some(branch) if imported.contains(branch) => Some(branch.clone()),
+            _ => imported.into_iter().next(),
+        }
+    }
+
+    /// Shows if the first revision of a branch was created by `cvs import` together with `1.1`:
+    /// `1.1` has the log of an import, the same date and the same content.
+    fn is_import_of(&self, vendor_first: &Num) -> bool {
+        match (self.deltas.get(&num![1, 1]), self.deltas.get(vendor_first)) {
+            (Some(trunk), Some(vendor)) => {
+                // In a re-headed file `1.1` may be stored as a diff from the vendor revision.
+                let same_content = if self.delta_base(&trunk.num).as_ref() == Some(vendor_first) {
+                    is_empty(&trunk.text)
+                } else {
+                    is_empty(&vendor.text)
+                };
+                trunk.log == "Initial revision\n"
+                    && date_fields(&trunk.date) == date_fields(&vendor.date)
+                    && same_content
+            }
+            _ => false,
+        }
+    }
+
+    /// Shows if the file has revisions only on the vendor branch, i.e. it was imported and never committed on trunk.
+    pub fn is_vendor_branch_only(&self) -> bool {
+        self.head == num![1, 1] && self.branch.is_some() && self.vendor_branch().is_some()
+    }
+
+    /// Shows if revision `1.1` is only an artifact of `cvs import`, having the same content as `1.1.1.1`.
+    fn is_import_artifact(&self, rev: &Num) -> bool {
+        match self.vendor_branch() {
+            Some(mut vendor_first) => {
+                vendor_first.numbers.push(1);
+                *rev == num![1, 1] && self.is_import_of(&vendor_first)
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the history of the trunk, from the oldest revision to the newest.
+    ///
+    /// If `graft_vendor_branch` is set, vendor branch revisions are grafted onto the trunk as cvs2svn does:
+    /// the vendor revisions which were the default branch (committed before the first trunk commit after
+    /// `1.1`, or all of them if the default branch is still the vendor branch) become trunk revisions, and
+    /// `1.1` is replaced by `1.1.1.1` if it is only an artifact of the import.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs, num, Num};
+    ///
+    /// let contents = std::fs::read_to_string("examples/vendor.c,v").unwrap();
+    /// let (_, rcs) = parse_rcs(contents.as_str()).unwrap();
+    /// assert_eq!(vec![num![1, 1], num![1, 2]], rcs.trunk_history(false).unwrap());
+    /// assert_eq!(
+    ///     vec![num![1, 1, 1, 1], num![1, 1, 1, 2], num![1, 2]],
+    ///     rcs.trunk_history(true).unwrap()
+    /// );
+    /// ```
+    pub fn trunk_history(&self, graft_vendor_branch: bool) -> Result<Vec<Num>, Error> {
+        let trunk = self.branch_revisions(&num![])?;
+        let vendor = match self.vendor_branch() {
+            Some(vendor) if graft_vendor_branch => vendor,
+            _ => return Ok(trunk),
+        };
+        let vendor_revisions = self.branch_revisions(&vendor)?;
+        let branch_point = vendor.get_branching_point();
+        let mut history = Vec::new();
+        let mut trunk = trunk.into_iter().peekable();
+        while let Some(num) = trunk.next_if(|num| num <= &branch_point) {
+            if !self.is_import_artifact(&num) {
+                history.push(num);
+            }
+        }
+        let end = match (&self.branch, trunk.peek()) {
+            (Some(_), _) | (None, None) => None,
+            (None, Some(next)) => Some(date_fields(&self.get_delta(next)?.date)),
+        };
+        for num in vendor_revisions {
+            let date = date_fields(&self.get_delta(&num)?.date);
+            if end.map_or(true, |end| date < end) {
+                history.push(num);
+            }
+        }
+        history.extend(trunk);
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn default_head() {
+        assert_eq!(
+            num![1, 2],
+            parse_example("vendor.c,v").default_head().unwrap()
+        );
+        assert_eq!(
+            num![2, 1],
+            parse_example("text1.txt,v").default_head().unwrap()
+        );
+    }
+
+    #[test]
+    fn vendor_branch() {
+        assert_eq!(
+            Some(num![1, 1, 1]),
+            parse_example("vendor.c,v").vendor_branch()
+        );
+        assert_eq!(
+            Some(num![1, 1, 1]),
+            parse_example("vendoronly.c,v").vendor_branch()
+        );
+        assert_eq!(None, parse_example("text1.txt,v").vendor_branch());
+
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.branch = Some(num![1, 2, 2]);
+        assert_eq!(None, rcs.vendor_branch());
+
+        let mut rcs = parse_example("vendor.c,v");
+        rcs.deltas.get_mut(&num![1, 1]).unwrap().log = "first\n".to_string();
+        assert_eq!(None, rcs.vendor_branch());
+    }
+
+    #[test]
+    fn is_vendor_branch_only() {
+        assert!(parse_example("vendoronly.c,v").is_vendor_branch_only());
+        assert!(!parse_example("vendor.c,v").is_vendor_branch_only());
+        assert!(!parse_example("text1.txt,v").is_vendor_branch_only());
+    }
+
+    #[test]
+    fn trunk_history() {
+        let rcs = parse_example("vendoronly.c,v");
+        assert_eq!(vec![num![1, 1]], rcs.trunk_history(false).unwrap());
+        assert_eq!(
+            vec![num![1, 1, 1, 1], num![1, 1, 1, 2]],
+            rcs.trunk_history(true).unwrap()
+        );
+        let rcs = parse_example("text1.txt,v");
+        assert_eq!(
+            rcs.trunk_history(false).unwrap(),
+            rcs.trunk_history(true).unwrap()
+        );
+    }
+}
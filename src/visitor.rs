@@ -0,0 +1,182 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+/// Callbacks called by the parser while it reads a comma-v file.
+///
+/// Every method has an empty default implementation, so a visitor implements only what it needs.
+/// The callbacks are called in the order of the file: admin, deltas, desc, and deltatexts.
+/// [parse_rcs](crate::parse_rcs) is a visitor too, which builds an [RcsData].
+///
+/// Example, counting lines added per author:
+/// ```rust
+/// use rcs_parser::{parse_rcs_with_visitor, Delta, DiffCommand, Num, Visitor};
+/// use std::collections::HashMap;
+///
+/// #[derive(Default)]
+/// struct AddedLines {
+///     authors: HashMap<Num, String>,
+///     added: HashMap<String, usize>,
+/// }
+///
+/// impl Visitor for AddedLines {
+///     fn on_delta(&mut self, delta: Delta) {
+///         self.authors.insert(delta.num, delta.author);
+///     }
+///     fn on_diff_command(&mut self, num: &Num, command: DiffCommand) {
+///         if let DiffCommand::Add(_, lines) = command {
+///             *self.added.entry(self.authors[num].clone()).or_default() += lines.len();
+///         }
+///     }
+/// }
+///
+/// let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+/// let mut visitor = AddedLines::default();
+/// parse_rcs_with_visitor(contents.as_str(), &mut visitor).unwrap();
+/// assert_eq!(14, visitor.added["dseres"]);
+/// ```
+#[allow(unused_variables)]
+pub trait Visitor {
+    /// Called after the admin part is parsed. Only the admin fields of `admin` are filled.
+    fn on_admin(&mut self, admin: RcsData) {}
+    /// Called for each symbolic name of admin, before [on_admin](Visitor::on_admin).
+    fn on_symbol(&mut self, name: &str, num: &Num) {}
+    /// Called for each delta. Log and text of the delta are empty.
+    fn on_delta(&mut self, delta: Delta) {}
+    /// Called with the description of the file.
+    fn on_desc(&mut self, desc: String) {}
+    /// Called with the log of a revision.
+    fn on_deltatext_log(&mut self, num: &Num, log: String) {}
+    /// Called with the full text of the head revision.
+    fn on_head_text(&mut self, num: &Num, text: String) {}
+    /// Called for each diff command of a revision, in the order of the file.
+    fn on_diff_command(&mut self, num: &Num, command: DiffCommand) {}
+}
+
+/// Visitor building an [RcsData] from the parsed parts.
+#[derive(Debug, Default)]
+pub(crate) struct RcsDataBuilder {
+    rcsdata: Option<RcsData>,
+    desc: String,
+    deltas: BTreeMap<Num, Delta>,
+}
+
+impl RcsDataBuilder {
+    /// Returns the built data. `None` is returned if admin was not visited.
+    pub(crate) fn build(self) -> Option<RcsData> {
+        let RcsDataBuilder {
+            rcsdata,
+            desc,
+            deltas,
+        } = self;
+        rcsdata.map(|mut rcsdata| {
+            rcsdata.desc = desc;
+            rcsdata.deltas = deltas;
+            rcsdata
+        })
+    }
+}
+
+impl Visitor for RcsDataBuilder {
+    fn on_admin(&mut self, admin: RcsData) {
+        self.rcsdata = Some(admin);
+    }
+
+    fn on_delta(&mut self, delta: Delta) {
+        self.deltas.insert(delta.num.clone(), delta);
+    }
+
+    fn on_desc(&mut self, desc: String) {
+        self.desc = desc;
+    }
+
+    fn on_deltatext_log(&mut self, num: &Num, log: String) {
+        if let Some(d) = self.deltas.get_mut(num) {
+            d.log = log;
+        }
+    }
+
+    fn on_head_text(&mut self, num: &Num, text: String) {
+        if let Some(d) = self.deltas.get_mut(num) {
+            d.text = Text::Head(text);
+        }
+    }
+
+    fn on_diff_command(&mut self, num: &Num, command: DiffCommand) {
+        if let Some(Delta {
+            text: Text::Diff(commands),
+            ..
+        }) = self.deltas.get_mut(num)
+        {
+            commands.push(command);
+        }
+    }
+}
+
+/// Drives a visitor with a parsed deltatext.
+pub(crate) fn visit_deltatext<V: Visitor + ?Sized>(visitor: &mut V, deltatext: DeltaText) {
+    let DeltaText { num, log, text } = deltatext;
+    visitor.on_deltatext_log(&num, log);
+    match text {
+        Text::Head(text) => visitor.on_head_text(&num, text),
+        Text::Diff(commands) => {
+            for command in commands {
+                visitor.on_diff_command(&num, command);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[derive(Default)]
+    struct Counter {
+        symbols: usize,
+        deltas: usize,
+        logs: usize,
+        commands: usize,
+        head: Option<Num>,
+    }
+
+    impl Visitor for Counter {
+        fn on_symbol(&mut self, _: &str, _: &Num) {
+            self.symbols += 1;
+        }
+        fn on_delta(&mut self, _: Delta) {
+            self.deltas += 1;
+        }
+        fn on_deltatext_log(&mut self, _: &Num, _: String) {
+            self.logs += 1;
+        }
+        fn on_head_text(&mut self, num: &Num, _: String) {
+            self.head = Some(num.clone());
+        }
+        fn on_diff_command(&mut self, _: &Num, _: DiffCommand) {
+            self.commands += 1;
+        }
+    }
+
+    #[test]
+    fn parse_rcs_with_visitor() {
+        let contents = std::fs::read_to_string("examples/text1.txt,v").unwrap();
+        let mut counter = Counter::default();
+        let (input, _) = crate::parse_rcs_with_visitor(contents.as_str(), &mut counter).unwrap();
+        assert_eq!("", input);
+        assert_eq!(4, counter.symbols);
+        assert_eq!(7, counter.deltas);
+        assert_eq!(7, counter.logs);
+        assert_eq!(Some(num![2, 1]), counter.head);
+        assert_eq!(16, counter.commands);
+    }
+
+    #[test]
+    fn reader_visit() {
+        let file = std::fs::File::open("examples/text1.txt,v").unwrap();
+        let mut counter = Counter::default();
+        RcsReader::from_reader(file).visit(&mut counter).unwrap();
+        assert_eq!(4, counter.symbols);
+        assert_eq!(7, counter.deltas);
+        assert_eq!(16, counter.commands);
+    }
+}
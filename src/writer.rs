@@ -0,0 +1,232 @@
+use crate::*;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Quotes a string with `@` as in comma-v files.
+fn quote(text: &str) -> String {
+    format!("@{}@", text.replace('@', "@@"))
+}
+
+/// Formats a date of a delta with two digit fields, e.g. `2021.04.10.09.38.42`.
+///
+/// Years before 2000 are written with two digits, as RCS does.
+fn format_delta_date(date: &Num) -> String {
+    let fields: Vec<String> = date
+        .numbers
+        .iter()
+        .enumerate()
+        .map(|(i, n)| match (i, n) {
+            (0, 1900..=1999) => format!("{:02}", n - 1900),
+            _ => format!("{:02}", n),
+        })
+        .collect();
+    fields.join(".")
+}
+
+/// Formats the text of a delta: the whole text for the head, diff commands for the others.
+fn format_text(text: &Text) -> String {
+    match text {
+        Text::Head(text) => text.clone(),
+        Text::Diff(commands) => {
+            let mut result = String::new();
+            for command in commands {
+                match command {
+                    DiffCommand::Delete(line, count) => {
+                        let _ = writeln!(result, "d{} {}", line, count);
+                    }
+                    DiffCommand::Add(line, lines) => {
+                        let _ = writeln!(result, "a{} {}", line, lines.len());
+                        result.extend(lines.iter().map(String::as_str));
+                    }
+                    DiffCommand::Head(lines) => result.extend(lines.iter().map(String::as_str)),
+                }
+            }
+            result
+        }
+    }
+}
+
+impl RcsData {
+    /// Returns the revisions in the order of the delta list of GNU RCS: a revision, the rest of its branch,
+    /// then its branches. The head comes first.
+    fn delta_order(&self) -> Vec<Num> {
+        let mut order = Vec::new();
+        let mut stack = vec![self.head.clone()];
+        while let Some(num) = stack.pop() {
+            if let Some(delta) = self.deltas.get(&num) {
+                order.push(num);
+                stack.extend(delta.branches.iter().rev().cloned());
+                stack.extend(delta.next.clone());
+            }
+        }
+        order
+    }
+
+    /// Returns the revisions in the order of the delta texts of GNU RCS: a revision, its branches from the
+    /// newest one, then the rest of its branch. The revision stored in full comes first, as the parser expects it:
+    /// it is the head, unless the file was re-headed.
+    fn text_order(&self) -> Vec<Num> {
+        let stored = self.stored_revision();
+        let mut order = Vec::new();
+        if self.deltas.contains_key(stored) {
+            order.push(stored.clone());
+        }
+        let mut stack = vec![self.head.clone()];
+        while let Some(num) = stack.pop() {
+            if let Some(delta) = self.deltas.get(&num) {
+                if num != *stored {
+                    order.push(num);
+                }
+                stack.extend(delta.next.clone());
+                stack.extend(delta.branches.iter().cloned());
+            }
+        }
+        order
+    }
+
+    /// Formats the file as comma-v text exactly as GNU RCS writes it.
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "head\t{};", self.head);
+        if let Some(branch) = &self.branch {
+            let _ = writeln!(out, "branch\t{};", branch);
+        }
+        out.push_str("access");
+        for id in self.access.iter() {
+            let _ = write!(out, "\n\t{}", id);
+        }
+        out.push_str(";\nsymbols");
+        for (name, num) in self.symbols.iter() {
+            let _ = write!(out, "\n\t{}:{}", name, num);
+        }
+        out.push_str(";\nlocks");
+        for (id, num) in self.locks.iter() {
+            let _ = write!(out, "\n\t{}:{}", id, num);
+        }
+        if self.strict {
+            out.push_str("; strict");
+        }
+        out.push_str(";\n");
+        if let Some(integrity) = &self.integrity {
+            let _ = writeln!(out, "integrity\t{};", quote(integrity));
+        }
+        if let Some(comment) = &self.comment {
+            let _ = writeln!(out, "comment\t{};", quote(comment));
+        }
+        if let Some(expand) = &self.expand {
+            let _ = writeln!(out, "expand\t{};", quote(expand));
+        }
+        out.push('\n');
+
+        for num in self.delta_order() {
+            let delta = &self.deltas[&num];
+            let _ = write!(
+                out,
+                "\n{}\ndate\t{};\tauthor {};\tstate {};\nbranches",
+                num,
+                format_delta_date(&delta.date),
+                delta.author,
+                delta.state.as_deref().unwrap_or_default()
+            );
+            for branch in delta.branches.iter() {
+                let _ = write!(out, "\n\t{}", branch);
+            }
+            let _ = writeln!(
+                out,
+                ";\nnext\t{};",
+                delta.next.as_ref().map(Num::to_string).unwrap_or_default()
+            );
+            if let Some(commitid) = &delta.commitid {
+                let _ = writeln!(out, "commitid\t{};", commitid);
+            }
+        }
+
+        let _ = writeln!(out, "\n\ndesc\n{}", quote(&self.desc));
+        for num in self.text_order() {
+            let delta = &self.deltas[&num];
+            let _ = writeln!(
+                out,
+                "\n\n{}\nlog\n{}\ntext\n{}",
+                num,
+                quote(&delta.log),
+                quote(&format_text(&delta.text))
+            );
+        }
+        out
+    }
+
+    /// Returns the content of the comma-v file, formatted byte by byte as GNU RCS formats it.
+    ///
+    /// Texts are converted to bytes by [text_to_bytes], so files parsed from bytes are written back byte by byte.
+    ///
+    /// ```rust
+    /// use rcs_parser::{parse_rcs_bytes, num, Num};
+    ///
+    /// let contents = std::fs::read("examples/text1.txt,v").unwrap();
+    /// let mut rcs = parse_rcs_bytes(&contents).unwrap();
+    /// rcs.set_state("1.1", "Exp").unwrap();
+    /// let written = parse_rcs_bytes(&rcs.to_bytes()).unwrap();
+    /// assert_eq!(rcs, written);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        text_to_bytes(&self.to_text())
+    }
+
+    /// Writes the comma-v file, see [to_bytes](RcsData::to_bytes).
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        out.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::parse_example;
+    use crate::*;
+
+    #[test]
+    fn to_bytes() {
+        for file in ["text1.txt,v", "vendor.c,v", "removed.c,v", "vendoronly.c,v"] {
+            let contents = std::fs::read(format!("examples/{}", file)).unwrap();
+            let rcs = parse_rcs_bytes(&contents).unwrap();
+            assert_eq!(
+                String::from_utf8_lossy(&contents),
+                String::from_utf8_lossy(&rcs.to_bytes()),
+                "{}",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn to_bytes_admin() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.add_access(&["alice", "bob"]).unwrap();
+        rcs.expand = Some("b".to_string());
+        rcs.deltas.get_mut(&num![1, 1]).unwrap().date = num![1999, 12, 31, 23, 59, 59];
+        let written = String::from_utf8(rcs.to_bytes()).unwrap();
+        assert!(
+            written.starts_with("head\t2.1;\naccess\n\talice\n\tbob;\nsymbols\n\tFix2:1.2.2.3\n")
+        );
+        assert!(written.contains("comment\t@# @;\nexpand\t@b@;\n\n\n2.1\n"));
+        assert!(written.contains("\ndate\t99.12.31.23.59.59;\tauthor dseres;"));
+        let (_, mut written) = parse_rcs(written.as_str()).unwrap();
+        written.deltas.get_mut(&num![1, 1]).unwrap().date = num![1999, 12, 31, 23, 59, 59];
+        assert_eq!(rcs, written);
+    }
+
+    #[test]
+    fn to_bytes_quoting() {
+        let mut rcs = parse_example("text1.txt,v");
+        rcs.desc = "user@example.com\n".to_string();
+        rcs.deltas.get_mut(&num![1, 1]).unwrap().log = "@@ mail @\n".to_string();
+        let written = String::from_utf8(rcs.to_bytes()).unwrap();
+        assert!(written.contains("@user@@example.com\n@"));
+        rcs.integrity = Some("a@b".to_string());
+        assert!(String::from_utf8(rcs.to_bytes())
+            .unwrap()
+            .contains("integrity\t@a@@b@;\n"));
+        rcs.integrity = None;
+        assert_eq!(rcs, parse_rcs(written.as_str()).unwrap().1);
+    }
+}